@@ -2,6 +2,19 @@ use crate::lox::printer::Visitor;
 use crate::lox::token::Token;
 use crate::lox::token_type::LiteralValue;
 
+/// 関数のパラメータ。
+///
+/// # フィールド
+/// - `name`: パラメータ名のトークン。
+/// - `default`: 引数が省略された場合に呼び出し時に評価されるデフォルト値の式。
+/// - `rest`: 残りの引数をリストとして受け取る可変長パラメータ（`...name`）かどうか。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: Token,
+    pub default: Option<Expr>,
+    pub rest: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Binary {
@@ -9,14 +22,28 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    /// 条件式 `condition ? then_branch : else_branch`。選ばれた分岐のみを評価します。
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
     Grouping {
         expression: Box<Expr>,
     },
     Literal {
         value: LiteralValue,
+        token: Token,
     },
     Variable {
         name: Token,
+        /// リゾルバが計算したスコープの距離。`None` の場合はグローバル変数として扱います。
+        depth: Option<usize>,
     },
     Unary {
         operator: Token,
@@ -25,9 +52,25 @@ pub enum Expr {
     Assign {
         name: Token,
         value: Box<Expr>,
+        /// リゾルバが計算したスコープの距離。`None` の場合はグローバル変数として扱います。
+        depth: Option<usize>,
+    },
+    /// 複合代入（`+=`、`-=`、`*=`、`/=`、`%=`）。対象は変数、プロパティ、添字のいずれかです。
+    CompoundAssign {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    /// インクリメント・デクリメント（`++`、`--`）。`prefix` が `false` の場合は後置です。
+    Increment {
+        target: Box<Expr>,
+        operator: Token,
+        prefix: bool,
     },
     Call {
         callee: Box<Expr>,
+        /// 引数リストを閉じる `)` のトークン。
+        paren: Token,
         arguments: Vec<Expr>,
     },
     Get {
@@ -39,6 +82,46 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>,
     },
+    List {
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
+    /// 呼び出しの引数に現れるスプレッド（`...list`）。リストの要素を個別の引数として展開します。
+    Spread {
+        ellipsis: Token,
+        expression: Box<Expr>,
+    },
+    Lambda {
+        keyword: Token,
+        params: Vec<Parameter>,
+        body: Vec<Stmt>,
+    },
+    /// 文字列補間。各部分を `print` と同じ形式で文字列化して連結します。
+    Interpolation {
+        parts: Vec<Expr>,
+    },
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    This {
+        keyword: Token,
+    },
+    Super {
+        keyword: Token,
+        method: Token,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,21 +147,35 @@ pub enum Stmt {
     },
     Function {
         name: Token,
-        params: Vec<Token>,
+        params: Vec<Parameter>,
         body: Vec<Stmt>,
     },
     Return {
         keyword: Token,
         value: Option<Expr>,
     },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
+    },
+    /// `throw` 文。任意の値を例外として送出します。
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    /// `try` 文。`catch` 節は例外を受け取る変数名と本体、`finally` 節は必ず実行される本体です。
+    Try {
+        body: Vec<Stmt>,
+        catch: Option<(Token, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+    },
     Class {
         name: Token,
+        superclass: Option<Expr>,
         methods: Vec<(Token, Stmt)>,
     },
-    Call {
-        callee: Box<Expr>,
-        arguments: Vec<Expr>,
-    },
     Assign {
         name: Token,
         value: Expr,
@@ -93,18 +190,88 @@ impl Expr {
                 operator,
                 right,
             } => visitor.visit_binary(left, operator, right),
-            Expr::Literal { value } => visitor.visit_literal(value),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_conditional(condition, then_branch, else_branch),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => visitor.visit_logical(left, operator, right),
+            Expr::Literal { value, .. } => visitor.visit_literal(value),
             Expr::Grouping { expression } => visitor.visit_grouping(expression),
-            Expr::Variable { name } => visitor.visit_variable(name),
+            Expr::Variable { name, .. } => visitor.visit_variable(name),
             Expr::Unary { operator, operand } => visitor.visit_unary(operator, operand),
-            Expr::Assign { name, value } => visitor.visit_assign(name, value),
-            Expr::Call { callee, arguments } => visitor.visit_call(callee, arguments),
+            Expr::Assign { name, value, .. } => visitor.visit_assign(name, value),
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => visitor.visit_compound_assign(target, operator, value),
+            Expr::Increment {
+                target,
+                operator,
+                prefix,
+            } => visitor.visit_increment(target, operator, *prefix),
+            Expr::Call {
+                callee, arguments, ..
+            } => visitor.visit_call(callee, arguments),
             Expr::Get { object, name } => visitor.visit_get(object, name),
             Expr::Set {
                 object,
                 name,
                 value,
             } => visitor.visit_set(object, name, value),
+            Expr::List { elements, .. } => visitor.visit_list(elements),
+            Expr::Spread { expression, .. } => visitor.visit_spread(expression),
+            Expr::Lambda { params, body, .. } => visitor.visit_lambda(params, body),
+            Expr::Interpolation { parts } => visitor.visit_interpolation(parts),
+            Expr::Map { entries, .. } => visitor.visit_map(entries),
+            Expr::Index { object, index, .. } => visitor.visit_index(object, index),
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => visitor.visit_set_index(object, index, value),
+            Expr::This { keyword } => visitor.visit_this(keyword),
+            Expr::Super { keyword, method } => visitor.visit_super(keyword, method),
+        }
+    }
+
+    /// 式のソースコード上の位置を代表するトークンを返します。
+    ///
+    /// 実行時エラーの発生箇所として使用します。演算子を持つ式は演算子、名前を持つ式は名前の
+    /// トークンを返し、グループ化や条件式などは内側の式のトークンを返します。
+    ///
+    /// # 戻り値
+    /// 代表するトークンがあれば `Some(&Token)`、なければ `None`。
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Expr::Binary { operator, .. }
+            | Expr::Logical { operator, .. }
+            | Expr::Unary { operator, .. }
+            | Expr::CompoundAssign { operator, .. }
+            | Expr::Increment { operator, .. } => Some(operator),
+            Expr::Variable { name, .. }
+            | Expr::Assign { name, .. }
+            | Expr::Get { name, .. }
+            | Expr::Set { name, .. } => Some(name),
+            Expr::Literal { token, .. } => Some(token),
+            Expr::Call { paren, .. } => Some(paren),
+            Expr::List { bracket, .. }
+            | Expr::Index { bracket, .. }
+            | Expr::SetIndex { bracket, .. } => Some(bracket),
+            Expr::Map { brace, .. } => Some(brace),
+            Expr::Spread { ellipsis, .. } => Some(ellipsis),
+            Expr::Lambda { keyword, .. } | Expr::This { keyword } | Expr::Super { keyword, .. } => {
+                Some(keyword)
+            }
+            Expr::Grouping { expression } => expression.token(),
+            Expr::Conditional { condition, .. } => condition.token(),
+            Expr::Interpolation { parts } => parts.first().and_then(Expr::token),
         }
     }
 }
@@ -134,8 +301,19 @@ impl Stmt {
             }
             Stmt::Function { name, params, body } => visitor.visit_function(name, params, body),
             Stmt::Return { keyword, value } => visitor.visit_return(keyword, value),
-            Stmt::Class { name, methods } => visitor.visit_class(name, methods),
-            Stmt::Call { callee, arguments } => visitor.visit_call(callee, arguments),
+            Stmt::Break { keyword } => visitor.visit_break(keyword),
+            Stmt::Continue { keyword } => visitor.visit_continue(keyword),
+            Stmt::Throw { keyword, value } => visitor.visit_throw(keyword, value),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+            } => visitor.visit_try(body, catch, finally),
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => visitor.visit_class(name, superclass, methods),
             Stmt::Assign { name, value } => visitor.visit_assign(name, value),
         }
     }
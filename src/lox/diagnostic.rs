@@ -0,0 +1,258 @@
+use crate::lox::error::LoxError;
+use crate::lox::token::Span;
+use std::io;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+/// 診断メッセージの重大度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// 実行を継続できないエラー。
+    Error,
+    /// 実行は継続できるが注意が必要な警告。
+    Warning,
+}
+
+impl Severity {
+    /// 表示に使用するラベルを返します。
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+
+    /// ラベルと下線の表示色を返します。
+    fn color(self) -> Color {
+        match self {
+            Severity::Error => Color::Red,
+            Severity::Warning => Color::Yellow,
+        }
+    }
+}
+
+/// ソースコードの抜粋とともに表示する診断メッセージ。
+///
+/// rustc と同様の形式で、発生箇所の行と `^` による下線、補足（`note`）やヒント（`help`）を表示します。
+///
+/// ```text
+/// error: Division by zero
+///  --> script.lox:3:12
+///   |
+/// 3 |   return x / 0;
+///   |            ^
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 重大度。
+    pub severity: Severity,
+    /// 診断の内容を表すメッセージ。
+    pub message: String,
+    /// 発生箇所。`None` の場合はソースコードの抜粋を表示しません。
+    pub span: Option<Span>,
+    /// 補足情報のリスト。
+    pub notes: Vec<String>,
+    /// 修正方法のヒント。
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// 新しい `Diagnostic` を作成します。
+    ///
+    /// # 引数
+    /// - `severity`: 重大度。
+    /// - `message`: 診断の内容を表すメッセージ。
+    ///
+    /// # 戻り値
+    /// 発生箇所、補足、ヒントを持たない `Diagnostic`。
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            span: None,
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// `LoxError` から診断メッセージのリストを作成します。
+    ///
+    /// 複数のエラーをまとめた `LoxError::Multiple` は、エラーごとの診断メッセージに展開します。
+    ///
+    /// # 引数
+    /// - `err`: 対象のエラー。
+    ///
+    /// # 戻り値
+    /// 発生した順の `Diagnostic` のリスト。
+    pub fn collect(err: &LoxError) -> Vec<Diagnostic> {
+        match err {
+            LoxError::Multiple(errors) => errors.iter().flat_map(Diagnostic::collect).collect(),
+            err => vec![Diagnostic::from(err)],
+        }
+    }
+
+    /// 発生箇所を設定します。
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// 補足情報を追加します。
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// 修正方法のヒントを設定します。
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// 診断メッセージを出力します。
+    ///
+    /// 色の有無は `out` の設定に従います（`termcolor::ColorChoice` を参照）。
+    ///
+    /// # 引数
+    /// - `source`: 発生箇所の行を取り出すソースコード。
+    /// - `out`: 出力先。
+    ///
+    /// # 戻り値
+    /// 出力に失敗した場合は `io::Error`。
+    pub fn render(&self, source: &str, out: &mut dyn WriteColor) -> io::Result<()> {
+        let gutter_color = ColorSpec::new()
+            .set_fg(Some(Color::Blue))
+            .set_bold(true)
+            .clone();
+        let severity_color = ColorSpec::new()
+            .set_fg(Some(self.severity.color()))
+            .set_bold(true)
+            .clone();
+
+        out.set_color(&severity_color)?;
+        write!(out, "{}", self.severity.label())?;
+        out.set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(out, ": {}", self.message)?;
+        out.reset()?;
+
+        let width = self
+            .span
+            .as_ref()
+            .map_or(0, |span| span.line.to_string().len());
+        let pad = " ".repeat(width);
+
+        if let Some(span) = &self.span {
+            out.set_color(&gutter_color)?;
+            write!(out, "{}--> ", pad)?;
+            out.reset()?;
+            writeln!(out, "{}", span)?;
+
+            if let Some(line) = source_line(source, span.line) {
+                out.set_color(&gutter_color)?;
+                writeln!(out, "{} |", pad)?;
+                write!(out, "{} | ", span.line)?;
+                out.reset()?;
+                writeln!(out, "{}", line)?;
+
+                let (indent, underline) = underline(line, span.column, span.length);
+                out.set_color(&gutter_color)?;
+                write!(out, "{} | ", pad)?;
+                out.reset()?;
+                write!(out, "{}", indent)?;
+                out.set_color(&severity_color)?;
+                writeln!(out, "{}", underline)?;
+                out.reset()?;
+            }
+        }
+
+        let labels = self
+            .notes
+            .iter()
+            .map(|note| ("note", note))
+            .chain(self.help.iter().map(|help| ("help", help)));
+        for (label, text) in labels {
+            out.set_color(&gutter_color)?;
+            write!(out, "{} = ", pad)?;
+            out.set_color(ColorSpec::new().set_bold(true))?;
+            write!(out, "{}", label)?;
+            out.reset()?;
+            writeln!(out, ": {}", text)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&LoxError> for Diagnostic {
+    /// `LoxError` から診断メッセージを作成します。
+    ///
+    /// 発生箇所を持つエラーはその位置を使用し、コールスタックは呼び出しごとの補足として表示します。
+    /// 一部のエラーには修正方法のヒントを付与します。
+    fn from(err: &LoxError) -> Self {
+        let mut diagnostic = Diagnostic::new(Severity::Error, err.message());
+        if let Some(span) = err.span() {
+            diagnostic = diagnostic.with_span(span.clone());
+        }
+        for line in err.trace_lines() {
+            diagnostic = diagnostic.with_note(line);
+        }
+
+        match err.root() {
+            LoxError::UndefinedVariable(name) => {
+                diagnostic.with_help(format!("declare '{}' with 'var' before using it", name))
+            }
+            LoxError::NonBooleanCondition(_) => diagnostic
+                .with_note("conditions must be 'true' or 'false' in strict mode")
+                .with_help("compare the value explicitly, for example 'x != nil'"),
+            LoxError::InvalidEscapeSequence(_) => diagnostic.with_help(
+                "supported escapes are \\n, \\t, \\r, \\0, \\\", \\\\, \\$ and \\u{...}",
+            ),
+            _ => diagnostic,
+        }
+    }
+}
+
+/// ソースコードから指定した行（1始まり）を取り出します。
+///
+/// スキャナーと同様に、先頭の BOM と行末の `\r` は取り除きます。
+fn source_line(source: &str, line: usize) -> Option<&str> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    source.lines().nth(line.checked_sub(1)?)
+}
+
+/// 下線の前の空白と、下線（`^`）の文字列を作成します。
+///
+/// 列番号と長さは文字単位で、表示幅が2の全角文字には2文字分の下線を引きます。
+/// タブは位置を揃えるためそのまま残し、行末を超える範囲は行末までに切り詰めます。
+fn underline(line: &str, column: usize, length: usize) -> (String, String) {
+    let start = column.saturating_sub(1);
+    let indent = line
+        .chars()
+        .take(start)
+        .map(|c| match c {
+            '\t' => "\t".to_string(),
+            c => " ".repeat(char_width(c)),
+        })
+        .collect();
+    let width: usize = line.chars().skip(start).take(length).map(char_width).sum();
+    (indent, "^".repeat(width.max(1)))
+}
+
+/// 端末上での文字の表示幅を返します。
+///
+/// 東アジアの全角文字（漢字、かな、ハングル、全角記号など）を2、それ以外を1とします。
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
@@ -1,3 +1,27 @@
+use crate::lox::evaluator::Value;
+use crate::lox::token::Span;
+
+/// 実行時のコールスタックの1フレーム。
+///
+/// `at 関数名 (ファイル名:行番号)` の形式で表示されます。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    /// 呼び出された関数の名前。
+    pub function: String,
+    /// 関数を呼び出した位置。
+    pub call_site: Span,
+}
+
+impl std::fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "at {} ({}:{})",
+            self.function, self.call_site.file, self.call_site.line
+        )
+    }
+}
+
 /// プロジェクト全体で使用する共通エラー型。
 ///
 /// この列挙型は、Loxインタプリタ全体で発生する可能性のあるさまざまなエラーを表します。
@@ -34,6 +58,18 @@ pub enum LoxError {
     /// - `String`: エラーの詳細メッセージ。
     UnterminatedString(String),
 
+    /// 文字列リテラル内の不正なエスケープシーケンスのエラー。
+    ///
+    /// # 引数
+    /// - `String`: エラーの詳細メッセージ。
+    InvalidEscapeSequence(String),
+
+    /// 不正な数値リテラルのエラー。
+    ///
+    /// # 引数
+    /// - `String`: エラーの詳細メッセージ。
+    MalformedNumber(String),
+
     /// ソースコードに予期しない文字が含まれている場合のエラー。
     ///
     /// # 引数
@@ -69,36 +105,235 @@ pub enum LoxError {
     /// # 引数
     /// - `String`: エラーの詳細メッセージ。
     RuntimeError(String),
+
+    /// 変数解決（静的解析）時に検出されたエラー。
+    ///
+    /// # 引数
+    /// - `String`: エラーの詳細メッセージ。
+    ResolveError(String),
+
+    /// `throw` 文で送出され、`catch` 節で捕捉されなかった値。
+    ///
+    /// # 引数
+    /// - `Value`: 送出された値。
+    Thrown(Value),
+
+    /// ソースコード上の発生箇所が特定されたエラー。
+    ///
+    /// # フィールド
+    /// - `span`: エラーの原因となったトークンの位置。
+    /// - `error`: 発生したエラー。
+    Located { span: Span, error: Box<LoxError> },
+
+    /// 関数の呼び出し中に発生した実行時エラーと、その時点のコールスタック。
+    ///
+    /// # フィールド
+    /// - `error`: 発生したエラー。
+    /// - `trace`: 最も内側の呼び出しを先頭とするコールスタック。
+    Traced {
+        error: Box<LoxError>,
+        trace: Vec<CallFrame>,
+    },
+
+    /// 一度の解析で見つかった複数のエラー。
+    ///
+    /// # 引数
+    /// - `Vec<LoxError>`: 見つかった順のエラーのリスト。
+    Multiple(Vec<LoxError>),
 }
 
-impl std::fmt::Display for LoxError {
-    /// エラーを人間が読みやすい形式でフォーマットします。
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl LoxError {
+    /// エラーに発生箇所を付与します。
+    ///
+    /// 既に発生箇所が付与されている場合は、より内側で特定された位置を優先してそのまま返します。
+    /// 複数のエラーをまとめた `LoxError::Multiple` は、それぞれが発生箇所を持つためそのまま返します。
+    ///
+    /// # 引数
+    /// - `span`: エラーの原因となったトークンの位置。
+    ///
+    /// # 戻り値
+    /// 発生箇所を持つ `LoxError::Located`。
+    pub fn at(self, span: Span) -> Self {
+        match self {
+            LoxError::Located { .. } | LoxError::Traced { .. } | LoxError::Multiple(_) => self,
+            error => LoxError::Located {
+                span,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// エラーの発生箇所を返します。
+    ///
+    /// # 戻り値
+    /// 発生箇所が付与されている場合は `Some(&Span)`、それ以外は `None`。
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            LoxError::Located { span, .. } => Some(span),
+            LoxError::Traced { error, .. } => error.span(),
+            _ => None,
+        }
+    }
+
+    /// エラーが発生した時点のコールスタックを返します。
+    ///
+    /// # 戻り値
+    /// 最も内側の呼び出しを先頭とするフレームのスライス。関数の外で発生したエラーでは空になります。
+    pub fn trace(&self) -> &[CallFrame] {
         match self {
-            LoxError::FileNotFound(file) => write!(f, "[Error: File not found '{}']", file),
-            LoxError::InvalidTypeConversion(msg) => {
-                write!(f, "[Error: Invalid type conversion '{}']", msg)
+            LoxError::Traced { trace, .. } => trace,
+            _ => &[],
+        }
+    }
+
+    /// コールスタックを表示用の行に変換します。
+    ///
+    /// 深い再帰でも表示が膨れないよう、同じ表示になるフレームが連続する場合は
+    /// 最初の1行と `... repeated N more times` の行にまとめます。
+    ///
+    /// # 戻り値
+    /// 最も内側の呼び出しから順に並んだ表示用の行。
+    pub fn trace_lines(&self) -> Vec<String> {
+        let frames: Vec<String> = self.trace().iter().map(CallFrame::to_string).collect();
+        let mut lines = Vec::new();
+        for group in frames.chunk_by(|a, b| a == b) {
+            lines.push(group[0].clone());
+            if group.len() > 1 {
+                lines.push(format!("... repeated {} more times", group.len() - 1));
             }
-            LoxError::IoError(msg) => write!(f, "[Error: IO error '{}']", msg),
-            LoxError::ParseError(msg) => write!(f, "[Error: Parse error '{}']", msg),
-            LoxError::UnterminatedString(msg) => {
-                write!(f, "[Error: Unterminated string '{}']", msg)
+        }
+        lines
+    }
+
+    /// エラーにコールスタックを付与します。
+    ///
+    /// 既にコールスタックが付与されている場合は、より深い位置で記録されたものを優先してそのまま返します。
+    ///
+    /// # 引数
+    /// - `trace`: 最も内側の呼び出しを先頭とするコールスタック。
+    ///
+    /// # 戻り値
+    /// コールスタックを持つ `LoxError::Traced`。
+    pub fn with_trace(self, trace: Vec<CallFrame>) -> Self {
+        match self {
+            LoxError::Traced { .. } | LoxError::Multiple(_) => self,
+            error => LoxError::Traced {
+                error: Box::new(error),
+                trace,
+            },
+        }
+    }
+
+    /// 発生箇所やコールスタックを除いた、エラーそのものを返します。
+    ///
+    /// # 戻り値
+    /// `LoxError::Located` や `LoxError::Traced` に包まれた内側のエラー。
+    pub fn root(&self) -> &LoxError {
+        match self {
+            LoxError::Located { error, .. } | LoxError::Traced { error, .. } => error.root(),
+            error => error,
+        }
+    }
+
+    /// 発生箇所や `[Error: ...]` の括りを除いた、エラーの内容を表すメッセージを返します。
+    ///
+    /// # 戻り値
+    /// エラーメッセージの文字列。
+    pub fn message(&self) -> String {
+        match self {
+            LoxError::FileNotFound(file) => format!("File not found '{}'", file),
+            LoxError::InvalidTypeConversion(msg) => format!("Invalid type conversion '{}'", msg),
+            LoxError::IoError(msg) => format!("IO error '{}'", msg),
+            LoxError::ParseError(msg) => format!("Parse error '{}'", msg),
+            LoxError::UnterminatedString(msg) => format!("Unterminated string '{}'", msg),
+            LoxError::InvalidEscapeSequence(msg) => format!("Invalid escape sequence '{}'", msg),
+            LoxError::MalformedNumber(msg) => format!("Malformed number literal '{}'", msg),
+            LoxError::UnexpectedCharacter(c) => format!("Unexpected character '{}'", c),
+            LoxError::UndefinedVariable(name) => format!("Undefined variable '{}'", name),
+            LoxError::DivisionByZero => "Division by zero".to_string(),
+            LoxError::NonBooleanCondition(cond) => format!("Non-boolean condition '{}'", cond),
+            LoxError::ReturnOutsideFunction => "Cannot return from outside a function.".to_string(),
+            LoxError::DuplicateParameterName(param) => {
+                format!("Duplicate parameter name '{}'", param)
             }
-            LoxError::UnexpectedCharacter(c) => write!(f, "[Error: Unexpected character '{}']", c),
-            LoxError::UndefinedVariable(name) => {
-                write!(f, "[Error: Undefined variable '{}']", name)
+            LoxError::RuntimeError(msg) => format!("Runtime error '{}'", msg),
+            LoxError::ResolveError(msg) => format!("Resolve error '{}'", msg),
+            LoxError::Thrown(value) => {
+                // `message` フィールドを持つインスタンス（捕捉したエラーの再送出など）はその内容を表示する
+                let message = match value {
+                    Value::Instance { fields, .. } => {
+                        fields.borrow().get("message").map(Value::to_string)
+                    }
+                    _ => None,
+                };
+                format!(
+                    "Uncaught exception '{}'",
+                    message.unwrap_or_else(|| value.to_string())
+                )
             }
-            LoxError::DivisionByZero => write!(f, "[Error: Division by zero]"),
-            LoxError::NonBooleanCondition(cond) => {
-                write!(f, "[Error: Non-boolean condition '{}']", cond)
+            LoxError::Located { error, .. } | LoxError::Traced { error, .. } => error.message(),
+            LoxError::Multiple(errors) => format!("{} errors found", errors.len()),
+        }
+    }
+
+    /// エラーの種類を表す接頭辞を除いた、エラーの説明文を返します。
+    ///
+    /// `catch` 節で受け取るエラーの `message` フィールドに使用します。
+    /// 例えば `RuntimeError("Stack overflow.")` に対しては `Stack overflow.` を返します。
+    ///
+    /// # 戻り値
+    /// エラーの説明文。説明文を別に持たないエラーでは `message` と同じ文字列になります。
+    pub fn detail(&self) -> String {
+        match self {
+            LoxError::InvalidTypeConversion(msg)
+            | LoxError::IoError(msg)
+            | LoxError::ParseError(msg)
+            | LoxError::NonBooleanCondition(msg)
+            | LoxError::RuntimeError(msg)
+            | LoxError::ResolveError(msg) => msg.clone(),
+            LoxError::Located { error, .. } | LoxError::Traced { error, .. } => error.detail(),
+            error => error.message(),
+        }
+    }
+
+    /// 発生箇所とコールスタックを保ったまま、エラーの内容を変換します。
+    ///
+    /// # 引数
+    /// - `f`: 発生箇所を除いたエラーを受け取り、新しいエラーを返す関数。
+    ///
+    /// # 戻り値
+    /// 変換後のエラー。元のエラーに発生箇所やコールスタックがあれば同じものが付与されます。
+    pub fn map(self, f: impl FnOnce(LoxError) -> LoxError) -> Self {
+        match self {
+            LoxError::Located { span, error } => f(*error).at(span),
+            LoxError::Traced { error, trace } => LoxError::Traced {
+                error: Box::new(error.map(f)),
+                trace,
+            },
+            error => f(error),
+        }
+    }
+}
+
+impl std::fmt::Display for LoxError {
+    /// エラーを人間が読みやすい形式でフォーマットします。
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoxError::Located { span, error } => {
+                write!(f, "[Error: {}: {}]", span, error.message())
             }
-            LoxError::ReturnOutsideFunction => {
-                write!(f, "[Error: Cannot return from outside a function.]")
+            LoxError::Traced { error, .. } => {
+                write!(f, "{}", error)?;
+                for line in self.trace_lines() {
+                    write!(f, "\n    {}", line)?;
+                }
+                Ok(())
             }
-            LoxError::DuplicateParameterName(param) => {
-                write!(f, "[Error: Duplicate parameter name '{}']", param)
+            LoxError::Multiple(errors) => {
+                let lines: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
             }
-            LoxError::RuntimeError(msg) => write!(f, "[Error: Runtime error '{}']", msg),
+            _ => write!(f, "[Error: {}]", self.message()),
         }
     }
 }
@@ -1,13 +1,33 @@
-use crate::lox::ast::{Expr, Stmt};
-use crate::lox::error::LoxError;
+use crate::lox::ast::{Expr, Parameter, Stmt};
+use crate::lox::error::{CallFrame, LoxError};
+use crate::lox::native;
 use crate::lox::token::Token;
 use crate::lox::token_type::{LiteralValue, TokenType};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 use std::time::SystemTime;
 
-#[derive(Debug, Clone, PartialEq)]
+/// `f64` で整数を正確に表せる範囲の上限（2^53）。
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// 関数呼び出しの深さの上限。これを超えると `Stack overflow.` の実行時エラーになります。
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// 式の評価前に確保しておくスタックの残り容量。不足していればスタックを拡張します。
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// スタックを拡張する際に新しく確保するスタックの大きさ。
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+/// 変数のスコープを表す環境。
+///
+/// 環境は `Rc<RefCell<Environment>>` として共有され、関数は定義時の環境をクロージャとして保持します。
+/// そのため、外側のスコープの変数への変更はすべての参照から観測できます。
+#[derive(Clone)]
 pub struct Environment {
-    enclosing: Option<Box<Environment>>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
     values: HashMap<String, Value>,
 }
 
@@ -21,9 +41,9 @@ impl Environment {
     }
 
     /// 指定された環境を囲む新しい環境を作成
-    pub fn with_enclosing(enclosing: Environment) -> Self {
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Environment {
-            enclosing: Some(Box::new(enclosing)),
+            enclosing: Some(enclosing),
             values: HashMap::new(),
         }
     }
@@ -33,32 +53,63 @@ impl Environment {
         self.values.insert(name, value);
     }
 
-    /// 子スコープの値を親スコープに統合する（現状では統合せずログ出力のみ）
-    pub fn merge_to_parent(&mut self) {
-        if let Some(parent) = &mut self.enclosing {
-            // 子スコープの値をマージせずにログ出力
-            for (key, value) in self.values.iter() {}
-        }
-    }
-
     /// 変数の値を取得（現在のスコープまたは親スコープを検索）
     pub fn get(&self, name: &str) -> Option<Value> {
         if let Some(value) = self.values.get(name) {
             Some(value.clone())
         } else if let Some(enclosing) = &self.enclosing {
-            enclosing.get(name)
+            enclosing.borrow().get(name)
         } else {
             None
         }
     }
 
-    /// 変数の値を更新（存在しない場合はエラーを返す）
+    /// 指定された距離だけ外側にある環境を取得
+    fn ancestor(env: &Rc<RefCell<Environment>>, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut current = Rc::clone(env);
+        for _ in 0..distance {
+            let enclosing =
+                current.borrow().enclosing.clone().expect(
+                    "Resolver produced a scope distance deeper than the environment chain.",
+                );
+            current = enclosing;
+        }
+        current
+    }
+
+    /// リゾルバが計算した距離にある環境から変数の値を取得
+    pub fn get_at(env: &Rc<RefCell<Environment>>, distance: usize, name: &str) -> Option<Value> {
+        Self::ancestor(env, distance)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+    }
+
+    /// リゾルバが計算した距離にある環境の変数の値を更新
+    pub fn assign_at(
+        env: &Rc<RefCell<Environment>>,
+        distance: usize,
+        name: String,
+        value: Value,
+    ) -> Result<(), String> {
+        let ancestor = Self::ancestor(env, distance);
+        let mut ancestor = ancestor.borrow_mut();
+        match ancestor.values.get_mut(&name) {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(format!("Variable '{}' not defined.", name)),
+        }
+    }
+
+    /// 変数の値を更新（変数が定義されている最も内側のスコープのみを書き換え、存在しない場合はエラーを返す）
     pub fn assign(&mut self, name: String, value: Value) -> Result<(), String> {
-        if self.values.contains_key(&name) {
-            self.values.insert(name, value);
-        } else if let Some(enclosing) = &mut self.enclosing {
-            self.values.insert(name.clone(), value.clone());
-            enclosing.assign(name, value)?;
+        if let Some(slot) = self.values.get_mut(&name) {
+            *slot = value;
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)?;
         } else {
             return Err(format!("Variable '{}' not defined.", name));
         }
@@ -66,6 +117,23 @@ impl Environment {
     }
 }
 
+/// 環境はクロージャを通じて自身を参照し得るため、変数名のみを出力します。
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Environment")
+            .field("names", &self.values.keys().collect::<Vec<_>>())
+            .field("has_enclosing", &self.enclosing.is_some())
+            .finish()
+    }
+}
+
+/// 環境は同一性で比較します（循環参照による無限再帰を避けるため）。
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Nil,
@@ -73,20 +141,235 @@ pub enum Value {
     Number(f64),
     String(String),
     Return(Box<Value>),
+    /// `break` 文によるループの中断を表す制御用の値
+    Break,
+    /// `continue` 文による次の繰り返しへの移行を表す制御用の値
+    Continue,
     Function {
         name: String,
-        params: Vec<Token>,
-        body: Vec<Stmt>,
+        params: Vec<Parameter>,
+        /// 関数本体。関数のすべての参照間で共有され、関数の同一性の判定にも使用します。
+        body: Rc<Vec<Stmt>>,
+        /// 関数が定義された時点の環境（クロージャ）。
+        closure: Rc<RefCell<Environment>>,
     },
     Class {
         name: String,
-        methods: HashMap<String, Value>,
+        superclass: Option<Box<Value>>,
+        /// メソッドの表。クラスのすべての参照間で共有され、クラスの同一性の判定にも使用します。
+        methods: Rc<HashMap<String, Value>>,
     },
+    /// クラスのインスタンス。フィールドはすべての参照間で共有されます。
     Instance {
         class: Box<Value>,
-        fields: HashMap<String, Value>,
+        fields: Rc<RefCell<HashMap<String, Value>>>,
     },
-    NativeFunction(fn(Vec<Value>) -> Value),
+    /// インスタンスに束縛されたメソッド。呼び出し時に `this` が `receiver` を指します。
+    BoundMethod {
+        receiver: Box<Value>,
+        method: Box<Value>,
+    },
+    /// 要素の並び。リストはすべての参照間で共有され、変更はすべての参照から観測できます。
+    List(Rc<RefCell<Vec<Value>>>),
+    /// キーと値の対応表。リストと同様にすべての参照間で共有されます。
+    Map(Rc<RefCell<BTreeMap<MapKey, Value>>>),
+    NativeFunction(fn(Vec<Value>) -> Result<Value, LoxError>),
+}
+
+impl Value {
+    /// 値をリストの添字として解釈します。
+    ///
+    /// # 引数
+    /// - `len`: 対象のリストの要素数。
+    ///
+    /// # 戻り値
+    /// - 成功時: `0 <= index < len` を満たす添字。
+    /// - 失敗時: 値が整数でない場合、または範囲外の場合の `LoxError::RuntimeError`。
+    pub(crate) fn as_index(&self, len: usize) -> Result<usize, LoxError> {
+        match self {
+            Value::Number(n) if n.fract() == 0.0 => {
+                if *n >= 0.0 && (*n as usize) < len {
+                    Ok(*n as usize)
+                } else {
+                    Err(LoxError::RuntimeError(format!(
+                        "Index {} out of range for length {}.",
+                        n, len
+                    )))
+                }
+            }
+            _ => Err(LoxError::RuntimeError(
+                "Index must be an integer.".to_string(),
+            )),
+        }
+    }
+
+    /// 数値を整数として解釈します。ビット演算の被演算子に使用します。
+    ///
+    /// # 戻り値
+    /// - 値が絶対値 2^53 以下（`f64` で正確に表せる範囲）の整数値の数値であれば `Some(整数)`。
+    /// - それ以外は `None`。
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    /// `==` 演算子の意味で2つの値を比較します。
+    ///
+    /// - 型が異なる値は等しくありません。
+    /// - 数値は IEEE 754 に従って比較します（`NaN` は自分自身とも等しくありません）。
+    /// - クラスとインスタンスは同一のオブジェクトである場合のみ等しくなります。
+    /// - 関数は同一の関数値（同じ宣言の評価によって作成されたもの）である場合のみ等しくなります。
+    /// - リストとマップは要素を再帰的に比較します。
+    ///
+    /// # 引数
+    /// - `other`: 比較対象の値。
+    ///
+    /// # 戻り値
+    /// - 等しい場合は `true`、それ以外は `false`。
+    pub fn is_equal(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Class { methods: a, .. }, Value::Class { methods: b, .. }) => Rc::ptr_eq(a, b),
+            (Value::Instance { fields: a, .. }, Value::Instance { fields: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            (
+                Value::Function {
+                    body: a_body,
+                    closure: a_closure,
+                    ..
+                },
+                Value::Function {
+                    body: b_body,
+                    closure: b_closure,
+                    ..
+                },
+            ) => Rc::ptr_eq(a_body, b_body) && Rc::ptr_eq(a_closure, b_closure),
+            (
+                Value::BoundMethod {
+                    receiver: a_receiver,
+                    method: a_method,
+                },
+                Value::BoundMethod {
+                    receiver: b_receiver,
+                    method: b_method,
+                },
+            ) => a_receiver.is_equal(b_receiver) && a_method.is_equal(b_method),
+            (Value::List(a), Value::List(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.is_equal(y))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b.iter())
+                        .all(|((ka, va), (kb, vb))| ka == kb && va.is_equal(vb))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// クラスとそのスーパークラスの連鎖からメソッドを検索します。
+    ///
+    /// # 引数
+    /// - `name`: メソッド名。
+    ///
+    /// # 戻り値
+    /// - メソッドが見つかった場合は `Some(Value::Function)`。
+    /// - 見つからない場合、または `self` がクラスでない場合は `None`。
+    fn find_method(&self, name: &str) -> Option<Value> {
+        match self {
+            Value::Class {
+                methods,
+                superclass,
+                ..
+            } => match methods.get(name) {
+                Some(method) => Some(method.clone()),
+                None => superclass
+                    .as_ref()
+                    .and_then(|superclass| superclass.find_method(name)),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// マップのキーとして使用できる値。
+///
+/// 文字列、数値、真偽値のみをキーとして許可します。数値は `-0` を `0` に正規化し、
+/// `NaN` はキーとして扱いません。キーの順序は真偽値、数値、文字列の順です。
+#[derive(Debug, Clone)]
+pub enum MapKey {
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+impl MapKey {
+    /// 値をマップのキーに変換します。
+    ///
+    /// # 引数
+    /// - `value`: キーとして使用する値。
+    ///
+    /// # 戻り値
+    /// - 成功時: 変換されたキー。
+    /// - 失敗時: キーとして使用できない値の場合の `LoxError::RuntimeError`。
+    pub fn from_value(value: &Value) -> Result<MapKey, LoxError> {
+        match value {
+            Value::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            Value::Number(n) if n.is_nan() => Err(LoxError::RuntimeError(
+                "NaN can't be used as a map key.".to_string(),
+            )),
+            // `-0` と `0` を同じキーとして扱う
+            Value::Number(n) => Ok(MapKey::Number(if *n == 0.0 { 0.0 } else { *n })),
+            Value::String(s) => Ok(MapKey::String(s.clone())),
+            _ => Err(LoxError::RuntimeError(
+                "Map keys must be strings, numbers or booleans.".to_string(),
+            )),
+        }
+    }
+
+    /// キーを値に戻します。
+    ///
+    /// # 戻り値
+    /// - キーに対応する `Value`。
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Boolean(b) => Value::Boolean(*b),
+            MapKey::Number(n) => Value::Number(*n),
+            MapKey::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MapKey {}
+
+impl PartialOrd for MapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (MapKey::Boolean(a), MapKey::Boolean(b)) => a.cmp(b),
+            (MapKey::Number(a), MapKey::Number(b)) => a.total_cmp(b),
+            (MapKey::String(a), MapKey::String(b)) => a.cmp(b),
+            (MapKey::Boolean(_), _) => Ordering::Less,
+            (_, MapKey::Boolean(_)) => Ordering::Greater,
+            (MapKey::Number(_), _) => Ordering::Less,
+            (_, MapKey::Number(_)) => Ordering::Greater,
+        }
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -95,13 +378,36 @@ impl std::fmt::Display for Value {
             Value::Nil => write!(f, "Nil"),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Number(n) => {
-                if n.fract() == 0.0 {
+                // 整数として正確に表せる範囲（2^53 未満）の整数値のみ小数部なしで表示する
+                if n.fract() == 0.0 && n.abs() < MAX_SAFE_INTEGER {
                     write!(f, "{}", *n as i64)
                 } else {
                     write!(f, "{}", n)
                 }
             }
             Value::String(s) => write!(f, "{}", s),
+            Value::Function { name, .. } => write!(f, "<fn {}>", name),
+            Value::Class { name, .. } => write!(f, "{}", name),
+            Value::Instance { class, .. } => write!(f, "{} instance", class),
+            Value::BoundMethod { method, .. } => write!(f, "{}", method),
+            Value::List(elements) => {
+                let elements = elements
+                    .borrow()
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "[{}]", elements)
+            }
+            Value::Map(entries) => {
+                let entries = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.to_value(), value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{{{}}}", entries)
+            }
             Value::NativeFunction(_) => write!(f, "<native fn>"),
             _ => write!(f, "Unsupported value"),
         }
@@ -114,8 +420,15 @@ pub enum EvalResult {
 }
 
 pub struct Evaluator {
-    environment: Environment,
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
     output: Vec<String>,
+    /// `true` の場合、条件式に真偽値以外を許可しない（厳格モード）。
+    strict_conditions: bool,
+    /// 実行中の関数呼び出しのスタック。最も内側の呼び出しが末尾になります。
+    call_stack: Vec<CallFrame>,
+    /// `catch` 節が受け取る実行時エラーのクラス。捕捉したすべてのエラーで共有します。
+    error_class: Value,
 }
 
 impl Evaluator {
@@ -125,6 +438,13 @@ impl Evaluator {
     ///
     /// # ネイティブ関数
     /// - `clock`: 現在のUNIXエポック時間を秒単位で返します。
+    /// - `len`: リストの要素数、または文字列の文字数を返します。
+    /// - `push`: リストの末尾に要素を追加します。
+    /// - `pop`: リストの末尾の要素を取り除いて返します。
+    /// - `slice`: リストの一部をコピーした新しいリストを返します。
+    /// - `keys`, `values`: マップのキー、値をリストとして返します。
+    /// - `has`: マップがキーを含むかどうかを返します。
+    /// - `remove`: マップからキーを取り除きます。
     ///
     /// # 戻り値
     /// 新しい `Evaluator` インスタンス。
@@ -139,18 +459,49 @@ impl Evaluator {
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .unwrap()
                     .as_secs();
-                Value::Number(time as f64)
+                Ok(Value::Number(time as f64))
             }),
         );
+        environment.define("len".to_string(), Value::NativeFunction(native::len));
+        environment.define("push".to_string(), Value::NativeFunction(native::push));
+        environment.define("pop".to_string(), Value::NativeFunction(native::pop));
+        environment.define("slice".to_string(), Value::NativeFunction(native::slice));
+        environment.define("keys".to_string(), Value::NativeFunction(native::keys));
+        environment.define("values".to_string(), Value::NativeFunction(native::values));
+        environment.define("has".to_string(), Value::NativeFunction(native::has));
+        environment.define("remove".to_string(), Value::NativeFunction(native::remove));
 
+        let globals = Rc::new(RefCell::new(environment));
         Self {
-            environment,
+            environment: Rc::clone(&globals),
+            globals,
             output: Vec::new(),
+            strict_conditions: false,
+            call_stack: Vec::new(),
+            error_class: Value::Class {
+                name: "Error".to_string(),
+                superclass: None,
+                methods: Rc::new(HashMap::new()),
+            },
         }
     }
 
+    /// 条件式の厳格モードを設定します。
+    ///
+    /// 既定では `if`、`while`、`for` の条件式は Lox の真偽判定（`nil` と `false` 以外は真）に従います。
+    /// 厳格モードでは条件式が真偽値以外に評価された場合に `LoxError::NonBooleanCondition` を返します。
+    ///
+    /// # 引数
+    /// - `strict`: 厳格モードを有効にする場合は `true`。
+    pub fn set_strict_conditions(&mut self, strict: bool) {
+        self.strict_conditions = strict;
+    }
+
     /// ステートメントのリストを評価します。
     ///
+    /// ローカル変数の参照を正しく解決するため、ステートメントは事前に
+    /// `Resolver::resolve` で解析されている必要があります。
+    ///
     /// # 引数
     /// - `statements`: 評価するステートメントのリスト
     ///
@@ -179,20 +530,15 @@ impl Evaluator {
         match stmt {
             Stmt::Expression(expr) => match self.evaluate(&expr) {
                 Ok(value) => EvalResult::Return(Value::Nil),
-                Err(err) => {
-                    let context = format!("Error occurred during expression evaluation: {:?}", err);
-                    EvalResult::Error(LoxError::InvalidTypeConversion(context))
-                }
+                // 送出された値や実行時エラーを `catch` 節がそのまま受け取れるよう、包み直さずに返す
+                Err(err) => EvalResult::Error(err),
             },
             Stmt::Print(expr) => match self.evaluate(&expr) {
                 Ok(value) => {
                     self.output.push(value.to_string());
                     EvalResult::Return(Value::Nil)
                 }
-                Err(err) => {
-                    self.output.push(format!("[Error: {}]", err));
-                    EvalResult::Error(err)
-                }
+                Err(err) => EvalResult::Error(err),
             },
             Stmt::Var { name, initializer } => {
                 let value = if let Some(init) = initializer {
@@ -206,81 +552,29 @@ impl Evaluator {
                     Value::Nil
                 };
 
-                self.environment.define(name.lexeme.clone(), value);
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), value);
                 EvalResult::Return(Value::Nil)
             }
             Stmt::Block(statements) => {
-                let enclosing = self.environment.clone();
-                let new_env = Environment::with_enclosing(enclosing);
-                let mut previous_env = std::mem::replace(&mut self.environment, new_env);
-
-                let mut last_result = EvalResult::Return(Value::Nil);
-
-                for stmt in statements {
-                    let result = self.execute(stmt);
-
-                    match result {
-                        EvalResult::Error(err) => {
-                            self.environment = previous_env;
-                            return EvalResult::Error(err);
-                        }
-                        EvalResult::Return(_) => last_result = result,
-                    }
-                }
-
-                self.environment.merge_to_parent();
-
-                if let Some(enclosing) = &self.environment.enclosing {
-                    previous_env.values.extend(enclosing.values.clone());
-                }
-
-                self.environment = previous_env;
-
-                last_result
-            }
-            Stmt::While(condition, body) => {
-                loop {
-                    match self.evaluate(&condition) {
-                        Ok(Value::Boolean(true)) => match self.execute(*body.clone()) {
-                            EvalResult::Error(err) => {
-                                return EvalResult::Error(err);
-                            }
-                            EvalResult::Return(_) => continue,
-                            _ => println!("Body executed successfully, continuing loop."),
-                        },
-                        Ok(Value::Boolean(false)) => {
-                            break;
-                        }
-                        Err(err) => {
-                            return EvalResult::Error(err);
-                        }
-                        _ => {
-                            return EvalResult::Error(LoxError::NonBooleanCondition(
-                                "Condition must evaluate to a boolean.".to_string(),
-                            ));
-                        }
-                    }
+                let new_env = Environment::with_enclosing(Rc::clone(&self.environment));
+                match self.execute_block(statements, Rc::new(RefCell::new(new_env))) {
+                    Ok(value) => EvalResult::Return(value),
+                    Err(err) => EvalResult::Error(err),
                 }
-                EvalResult::Return(Value::Nil)
             }
+            Stmt::While(condition, body) => self.execute_loop(Some(&condition), &body, None),
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
-            } => match self.evaluate(&condition) {
-                Ok(Value::Boolean(true)) => self.execute(*then_branch),
-
-                Ok(Value::Boolean(false)) => else_branch
-                    .map_or(EvalResult::Return(Value::Nil), |branch| {
-                        self.execute(*branch)
-                    }),
-
-                Err(err) => EvalResult::Error(err),
+            } => match self.evaluate_condition(&condition) {
+                Ok(true) => self.execute(*then_branch),
+                Ok(false) => else_branch.map_or(EvalResult::Return(Value::Nil), |branch| {
+                    self.execute(*branch)
+                }),
                 Err(err) => EvalResult::Error(err),
-
-                _ => EvalResult::Error(LoxError::NonBooleanCondition(
-                    "Condition must evaluate to a boolean.".to_string(),
-                )),
             },
             Stmt::For {
                 initializer,
@@ -288,45 +582,59 @@ impl Evaluator {
                 increment,
                 body,
             } => {
-                if let Some(initializer) = initializer {
-                    if let EvalResult::Error(err) = self.execute(*initializer) {
-                        return EvalResult::Error(err);
-                    }
-                }
+                // 初期化式で宣言された変数はループ専用のスコープに閉じ込める
+                let loop_env = Environment::with_enclosing(Rc::clone(&self.environment));
+                let previous_env =
+                    std::mem::replace(&mut self.environment, Rc::new(RefCell::new(loop_env)));
 
-                let condition_expr = condition.unwrap_or_else(|| Expr::Literal {
-                    value: LiteralValue::Boolean(true),
-                });
+                let result = match initializer.map(|initializer| self.execute(*initializer)) {
+                    Some(EvalResult::Error(err)) => EvalResult::Error(err),
+                    _ => self.execute_loop(condition.as_ref(), &body, increment.as_ref()),
+                };
+
+                self.environment = previous_env;
+                result
+            }
+            Stmt::Break { .. } => EvalResult::Return(Value::Break),
+            Stmt::Continue { .. } => EvalResult::Return(Value::Continue),
+            Stmt::Throw { keyword, value } => match self.evaluate(&value) {
+                Ok(value) => EvalResult::Error(LoxError::Thrown(value).at(keyword.span())),
+                Err(err) => EvalResult::Error(err),
+            },
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                let env = Environment::with_enclosing(Rc::clone(&self.environment));
+                let result = self.execute_block(body, Rc::new(RefCell::new(env)));
 
-                while let Ok(Value::Boolean(true)) = self.evaluate(&condition_expr) {
-                    match self.execute(*body.clone()) {
-                        EvalResult::Error(err) => return EvalResult::Error(err),
-                        _ => {}
+                let result = match (result, catch) {
+                    (Err(err), Some((name, statements))) => {
+                        let mut env = Environment::with_enclosing(Rc::clone(&self.environment));
+                        env.define(name.lexeme, self.exception_value(err));
+                        self.execute_block(statements, Rc::new(RefCell::new(env)))
                     }
+                    (result, _) => result,
+                };
 
-                    if let Some(increment) = &increment {
-                        if let Err(err) = self.evaluate(increment) {
-                            return EvalResult::Error(err);
+                // `finally` は `return` などで抜ける場合も実行し、その中の制御やエラーが優先される
+                let result = match finally {
+                    Some(statements) => {
+                        let env = Environment::with_enclosing(Rc::clone(&self.environment));
+                        match self.execute_block(statements, Rc::new(RefCell::new(env))) {
+                            Ok(control @ (Value::Return(_) | Value::Break | Value::Continue)) => {
+                                Ok(control)
+                            }
+                            Ok(_) => result,
+                            Err(err) => Err(err),
                         }
                     }
-                }
-
-                EvalResult::Return(Value::Nil)
-            }
-            Stmt::Call { callee, arguments } => {
-                let function = match self.evaluate(&callee) {
-                    Ok(value) => value,
-                    Err(err) => return EvalResult::Error(err),
+                    None => result,
                 };
 
-                let argument_values: Result<Vec<_>, _> =
-                    arguments.iter().map(|arg| self.evaluate(arg)).collect();
-
-                match argument_values {
-                    Ok(values) => match self.evaluate_call(function, values) {
-                        Ok(value) => EvalResult::Return(value),
-                        Err(err) => EvalResult::Error(err),
-                    },
+                match result {
+                    Ok(value) => EvalResult::Return(value),
                     Err(err) => EvalResult::Error(err),
                 }
             }
@@ -334,9 +642,62 @@ impl Evaluator {
                 let function = Value::Function {
                     name: name.lexeme.clone(),
                     params,
-                    body,
+                    body: Rc::new(body),
+                    closure: Rc::clone(&self.environment),
                 };
-                self.environment.define(name.lexeme.clone(), function);
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), function);
+                EvalResult::Return(Value::Nil)
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let superclass = match superclass {
+                    Some(expr) => match self.evaluate(&expr) {
+                        Ok(class @ Value::Class { .. }) => Some(Box::new(class)),
+                        Ok(_) => {
+                            return EvalResult::Error(Self::locate(
+                                LoxError::RuntimeError("Superclass must be a class.".to_string()),
+                                &expr,
+                            ))
+                        }
+                        Err(err) => return EvalResult::Error(err),
+                    },
+                    None => None,
+                };
+                // スーパークラスを持つ場合、メソッドは `super` を定義した環境を閉包する
+                let closure = match &superclass {
+                    Some(class) => {
+                        let mut env = Environment::with_enclosing(Rc::clone(&self.environment));
+                        env.define("super".to_string(), (**class).clone());
+                        Rc::new(RefCell::new(env))
+                    }
+                    None => Rc::clone(&self.environment),
+                };
+                let methods = methods
+                    .into_iter()
+                    .filter_map(|(method_name, method)| match method {
+                        Stmt::Function { name, params, body } => Some((
+                            method_name.lexeme,
+                            Value::Function {
+                                name: name.lexeme,
+                                params,
+                                body: Rc::new(body),
+                                closure: Rc::clone(&closure),
+                            },
+                        )),
+                        _ => None,
+                    })
+                    .collect();
+                let class = Value::Class {
+                    name: name.lexeme.clone(),
+                    superclass,
+                    methods: Rc::new(methods),
+                };
+                self.environment.borrow_mut().define(name.lexeme, class);
                 EvalResult::Return(Value::Nil)
             }
             Stmt::Return { value, .. } => {
@@ -351,30 +712,67 @@ impl Evaluator {
             }
             Stmt::Assign { name, value } => match self.evaluate(&value) {
                 Ok(val) => {
-                    if let Err(_) = self.environment.assign(name.lexeme.clone(), val.clone()) {
-                        return EvalResult::Error(LoxError::UndefinedVariable(name.lexeme.clone()));
+                    if self
+                        .environment
+                        .borrow_mut()
+                        .assign(name.lexeme.clone(), val)
+                        .is_err()
+                    {
+                        return EvalResult::Error(
+                            LoxError::UndefinedVariable(name.lexeme.clone()).at(name.span()),
+                        );
                     }
                     EvalResult::Return(Value::Nil)
                 }
                 Err(err) => EvalResult::Error(err),
             },
-            _ => EvalResult::Error(LoxError::InvalidTypeConversion(
-                "Unsupported statement.".to_string(),
-            )),
         }
     }
 
     /// 式を評価します。
     ///
+    /// 評価中のエラーに発生箇所が特定されていない場合は、この式の位置を付与します。
+    /// 深い再帰でもホストのスタックが溢れないよう、残り容量が少なければスタックを拡張してから評価します。
+    ///
     /// # 引数
     /// - `expr`: 評価対象の式。
     ///
     /// # 戻り値
     /// - 成功時: 評価結果 `Value` を含む `Ok`。
-    /// - 失敗時: エラー `LoxError` を含む `Err`。
+    /// - 失敗時: 発生箇所を持つエラー `LoxError` を含む `Err`。
     fn evaluate(&mut self, expr: &Expr) -> Result<Value, LoxError> {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            self.evaluate_expr(expr)
+                .map_err(|err| Self::locate(err, expr))
+        })
+    }
+
+    /// 発生箇所が特定されていないエラーに、式の位置を付与します。
+    ///
+    /// # 引数
+    /// - `err`: 対象のエラー。
+    /// - `expr`: エラーの原因となった式。
+    ///
+    /// # 戻り値
+    /// 式の位置を持つ `LoxError`。式が位置を持たない場合はそのまま返します。
+    fn locate(err: LoxError, expr: &Expr) -> LoxError {
+        match expr.token() {
+            Some(token) => err.at(token.span()),
+            None => err,
+        }
+    }
+
+    /// 式の種類に応じて評価を行います。
+    ///
+    /// # 引数
+    /// - `expr`: 評価対象の式。
+    ///
+    /// # 戻り値
+    /// - 成功時: 評価結果 `Value` を含む `Ok`。
+    /// - 失敗時: エラー `LoxError` を含む `Err`。
+    fn evaluate_expr(&mut self, expr: &Expr) -> Result<Value, LoxError> {
         match expr {
-            Expr::Literal { value } => self.literal_to_value(value.clone()),
+            Expr::Literal { value, .. } => self.literal_to_value(value.clone()),
 
             Expr::Unary { operator, operand } => {
                 let right = self.evaluate(operand)?;
@@ -386,6 +784,12 @@ impl Evaluator {
                         )),
                     },
                     TokenType::Bang => Ok(Value::Boolean(!self.is_truthy(right))),
+                    TokenType::Tilde => match right.as_integer() {
+                        Some(n) => Ok(Value::Number(!n as f64)),
+                        None => Err(LoxError::InvalidTypeConversion(
+                            "Operand must be an integer for '~'.".to_string(),
+                        )),
+                    },
                     _ => Err(LoxError::InvalidTypeConversion(
                         "Invalid unary operator.".to_string(),
                     )),
@@ -399,109 +803,370 @@ impl Evaluator {
             } => {
                 let left_value = self.evaluate(left)?;
                 let right_value = self.evaluate(right)?;
+                self.binary_operation(operator, left_value, right_value)
+            }
+
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate_condition(condition)? {
+                    self.evaluate(then_branch)
+                } else {
+                    self.evaluate(else_branch)
+                }
+            }
+
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left_value = self.evaluate(left)?;
+                // 短絡評価: 結果が左辺で決まる場合は右辺を評価せずに左辺の値を返す
+                let left_truthy = self.is_truthy(left_value.clone());
                 match operator.token_type {
-                    TokenType::Plus => match (left_value, right_value) {
-                        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
-                        (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
-                        _ => Err(LoxError::InvalidTypeConversion(
-                            "Operands must be two numbers or two strings for '+'.".to_string(),
-                        )),
-                    },
-                    TokenType::Minus => match (left_value, right_value) {
-                        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l - r)),
-                        _ => Err(LoxError::InvalidTypeConversion(
-                            "Operands must be numbers for '-'.".to_string(),
-                        )),
-                    },
-                    TokenType::Star => match (left_value, right_value) {
-                        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l * r)),
-                        _ => Err(LoxError::InvalidTypeConversion(
-                            "Operands must be numbers for '*'.".to_string(),
-                        )),
-                    },
-                    TokenType::Slash => match (left_value, right_value) {
-                        (Value::Number(l), Value::Number(r)) => {
-                            if r == 0.0 {
-                                Err(LoxError::DivisionByZero)
-                            } else {
-                                Ok(Value::Number(l / r))
-                            }
-                        }
-                        _ => Err(LoxError::InvalidTypeConversion(
-                            "Operands must be numbers for '/'.".to_string(),
-                        )),
-                    },
-                    TokenType::Less
-                    | TokenType::LessEqual
-                    | TokenType::Greater
-                    | TokenType::GreaterEqual => match (left_value, right_value) {
-                        (Value::Number(l), Value::Number(r)) => match operator.token_type {
-                            TokenType::Less => Ok(Value::Boolean(l < r)),
-                            TokenType::LessEqual => Ok(Value::Boolean(l <= r)),
-                            TokenType::Greater => Ok(Value::Boolean(l > r)),
-                            TokenType::GreaterEqual => Ok(Value::Boolean(l >= r)),
-                            _ => unreachable!(),
-                        },
-                        _ => Err(LoxError::InvalidTypeConversion(
-                            "Operands must be numbers for comparison.".to_string(),
-                        )),
-                    },
+                    TokenType::Or if left_truthy => Ok(left_value),
+                    TokenType::And if !left_truthy => Ok(left_value),
+                    TokenType::Or | TokenType::And => self.evaluate(right),
                     _ => Err(LoxError::InvalidTypeConversion(
-                        "Invalid binary operator.".to_string(),
+                        "Invalid logical operator.".to_string(),
                     )),
                 }
             }
 
-            Expr::Variable { name } => self
-                .environment
-                .get(&name.lexeme)
-                .ok_or_else(|| LoxError::UndefinedVariable(name.lexeme.clone())),
+            Expr::Variable { name, depth } => {
+                let value = match depth {
+                    Some(distance) => {
+                        Environment::get_at(&self.environment, *distance, &name.lexeme)
+                    }
+                    None => self.globals.borrow().get(&name.lexeme),
+                };
+                value.ok_or_else(|| LoxError::UndefinedVariable(name.lexeme.clone()))
+            }
 
             Expr::Grouping { expression } => self.evaluate(expression),
 
-            Expr::Assign { name, value } => {
+            Expr::Assign { name, value, depth } => {
                 let val = self.evaluate(value)?;
-                self.environment
-                    .assign(name.lexeme.clone(), val.clone())
-                    .map_err(|_| LoxError::UndefinedVariable(name.lexeme.clone()))?;
+                match depth {
+                    Some(distance) => Environment::assign_at(
+                        &self.environment,
+                        *distance,
+                        name.lexeme.clone(),
+                        val.clone(),
+                    ),
+                    None => self
+                        .globals
+                        .borrow_mut()
+                        .assign(name.lexeme.clone(), val.clone()),
+                }
+                .map_err(|_| LoxError::UndefinedVariable(name.lexeme.clone()))?;
                 Ok(val)
             }
 
-            Expr::Call { callee, arguments } => {
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => {
+                // `+=` を `+` のように、対応する二項演算子のトークンに変換する
+                let binary_operator = Token {
+                    token_type: match operator.token_type {
+                        TokenType::PlusEqual => TokenType::Plus,
+                        TokenType::MinusEqual => TokenType::Minus,
+                        TokenType::StarEqual => TokenType::Star,
+                        TokenType::SlashEqual => TokenType::Slash,
+                        _ => TokenType::Percent,
+                    },
+                    lexeme: operator.lexeme.trim_end_matches('=').to_string(),
+                    ..operator.clone()
+                };
+                let (_, new_value) = self.update_target(target, |evaluator, current| {
+                    let operand = evaluator.evaluate(value)?;
+                    evaluator.binary_operation(&binary_operator, current, operand)
+                })?;
+                Ok(new_value)
+            }
+
+            Expr::Increment {
+                target,
+                operator,
+                prefix,
+            } => {
+                let delta = if operator.token_type == TokenType::PlusPlus {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let (old_value, new_value) =
+                    self.update_target(target, |_, current| match current {
+                        Value::Number(n) => Ok(Value::Number(n + delta)),
+                        _ => Err(LoxError::InvalidTypeConversion(format!(
+                            "Operand must be a number for '{}'.",
+                            operator.lexeme
+                        ))),
+                    })?;
+                Ok(if *prefix { new_value } else { old_value })
+            }
+
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
                 let function = self.evaluate(callee)?;
-                let argument_values: Result<Vec<_>, _> =
-                    arguments.iter().map(|arg| self.evaluate(arg)).collect();
-                self.evaluate_call(function, argument_values?)
+                let argument_values = self.evaluate_arguments(arguments)?;
+                // 引数の数の誤りは呼び出し先ではなく呼び出し位置のエラーとする
+                Self::check_arity(&function, argument_values.len())
+                    .map_err(|err| err.at(paren.span()))?;
+                if self.call_stack.len() >= MAX_CALL_DEPTH {
+                    return Err(LoxError::RuntimeError("Stack overflow.".to_string())
+                        .at(paren.span())
+                        .with_trace(self.call_stack.iter().rev().cloned().collect()));
+                }
+
+                self.call_stack.push(CallFrame {
+                    function: Self::frame_name(&function, callee),
+                    call_site: callee.token().unwrap_or(paren).span(),
+                });
+                // 呼び出し中に発生したエラーには、最も深い位置でのコールスタックを付与する
+                let result = self
+                    .evaluate_call(function, argument_values)
+                    .map_err(|err| {
+                        err.at(paren.span())
+                            .with_trace(self.call_stack.iter().rev().cloned().collect())
+                    });
+                self.call_stack.pop();
+                result
             }
 
-            _ => Err(LoxError::InvalidTypeConversion(
-                "Unsupported expression.".to_string(),
+            Expr::Spread { .. } => Err(LoxError::RuntimeError(
+                "Spread is only allowed in call arguments.".to_string(),
             )),
+
+            Expr::Get { object, name } => {
+                let object = self.evaluate(object)?;
+                match &object {
+                    Value::Instance { class, fields } => {
+                        if let Some(value) = fields.borrow().get(&name.lexeme) {
+                            return Ok(value.clone());
+                        }
+                        match class.find_method(&name.lexeme) {
+                            Some(method) => Ok(Value::BoundMethod {
+                                receiver: Box::new(object.clone()),
+                                method: Box::new(method),
+                            }),
+                            None => Err(LoxError::RuntimeError(format!(
+                                "Undefined property '{}'.",
+                                name.lexeme
+                            ))),
+                        }
+                    }
+                    _ => Err(LoxError::RuntimeError(
+                        "Only instances have properties.".to_string(),
+                    )),
+                }
+            }
+
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                let object = self.evaluate(object)?;
+                let Value::Instance { fields, .. } = object else {
+                    return Err(LoxError::RuntimeError(
+                        "Only instances have fields.".to_string(),
+                    ));
+                };
+                let value = self.evaluate(value)?;
+                fields
+                    .borrow_mut()
+                    .insert(name.lexeme.clone(), value.clone());
+                Ok(value)
+            }
+
+            Expr::List { elements, .. } => {
+                let values = elements
+                    .iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::List(Rc::new(RefCell::new(values))))
+            }
+
+            Expr::Lambda { params, body, .. } => Ok(Value::Function {
+                name: "lambda".to_string(),
+                params: params.clone(),
+                body: Rc::new(body.clone()),
+                closure: Rc::clone(&self.environment),
+            }),
+
+            Expr::Interpolation { parts } => {
+                let mut result = String::new();
+                for part in parts {
+                    result.push_str(&self.evaluate(part)?.to_string());
+                }
+                Ok(Value::String(result))
+            }
+
+            Expr::Map { entries, .. } => {
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    let key = MapKey::from_value(&self.evaluate(key)?)?;
+                    let value = self.evaluate(value)?;
+                    map.insert(key, value);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+
+            Expr::Index { object, index, .. } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                match object {
+                    Value::List(elements) => {
+                        let elements = elements.borrow();
+                        Ok(elements[index.as_index(elements.len())?].clone())
+                    }
+                    Value::Map(entries) => entries
+                        .borrow()
+                        .get(&MapKey::from_value(&index)?)
+                        .cloned()
+                        .ok_or_else(|| {
+                            LoxError::RuntimeError(format!("Undefined key '{}'.", index))
+                        }),
+                    _ => Err(LoxError::RuntimeError(
+                        "Only lists and maps can be indexed.".to_string(),
+                    )),
+                }
+            }
+
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                let value = self.evaluate(value)?;
+                match object {
+                    Value::List(elements) => {
+                        let mut elements = elements.borrow_mut();
+                        let position = index.as_index(elements.len())?;
+                        elements[position] = value.clone();
+                        Ok(value)
+                    }
+                    Value::Map(entries) => {
+                        let key = MapKey::from_value(&index)?;
+                        entries.borrow_mut().insert(key, value.clone());
+                        Ok(value)
+                    }
+                    _ => Err(LoxError::RuntimeError(
+                        "Only lists and maps can be indexed.".to_string(),
+                    )),
+                }
+            }
+
+            Expr::This { .. } => self.environment.borrow().get("this").ok_or_else(|| {
+                LoxError::RuntimeError("Can't use 'this' outside of a class.".to_string())
+            }),
+
+            Expr::Super { method, .. } => {
+                let superclass = self.environment.borrow().get("super").ok_or_else(|| {
+                    LoxError::RuntimeError(
+                        "Can't use 'super' in a class with no superclass.".to_string(),
+                    )
+                })?;
+                let receiver = self.environment.borrow().get("this").ok_or_else(|| {
+                    LoxError::RuntimeError("Can't use 'super' outside of a class.".to_string())
+                })?;
+                match superclass.find_method(&method.lexeme) {
+                    Some(function) => Ok(Value::BoundMethod {
+                        receiver: Box::new(receiver),
+                        method: Box::new(function),
+                    }),
+                    None => Err(LoxError::RuntimeError(format!(
+                        "Undefined property '{}'.",
+                        method.lexeme
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// ループの本体を条件が偽になるまで繰り返し実行します。
+    ///
+    /// `break` でループを抜け、`continue` では本体の残りを飛ばして増分式の評価に進みます。
+    /// `return` はループを抜けて呼び出し元へ伝播します。
+    ///
+    /// # 引数
+    /// - `condition`: ループの継続条件。`None` の場合（条件を省略した `for` 文）は常に継続します。
+    /// - `body`: ループの本体。
+    /// - `increment`: 各繰り返しの最後に評価する増分式（`for` 文の場合）。
+    ///
+    /// # 戻り値
+    /// - `EvalResult`: 評価結果（`Return` または `Error`）。
+    fn execute_loop(
+        &mut self,
+        condition: Option<&Expr>,
+        body: &Stmt,
+        increment: Option<&Expr>,
+    ) -> EvalResult {
+        loop {
+            match condition.map_or(Ok(true), |condition| self.evaluate_condition(condition)) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => return EvalResult::Error(err),
+            }
+
+            match self.execute(body.clone()) {
+                EvalResult::Error(err) => return EvalResult::Error(err),
+                EvalResult::Return(Value::Break) => break,
+                // `return` 文はループを抜けて呼び出し元へ伝播させる
+                EvalResult::Return(Value::Return(value)) => {
+                    return EvalResult::Return(Value::Return(value));
+                }
+                EvalResult::Return(_) => {}
+            }
+
+            if let Some(increment) = increment {
+                if let Err(err) = self.evaluate(increment) {
+                    return EvalResult::Error(err);
+                }
+            }
         }
+        EvalResult::Return(Value::Nil)
     }
 
     /// ブロックを実行します。
     ///
+    /// `return`、`break`、`continue` 文が実行された場合は残りのステートメントを実行せず、
+    /// 制御用の値（`Value::Return`、`Value::Break`、`Value::Continue`）のまま呼び出し元へ返します。
+    ///
     /// # 引数
     /// - `statements`: 実行するステートメントのリスト。
-    /// - `new_env`: ブロック専用の新しい環境。
+    /// - `new_env`: ブロック専用の新しい環境。関数呼び出しでは引数を束縛済みの環境を渡します。
     ///
     /// # 戻り値
-    /// - 成功時: 最後に評価された値を含む `Ok`。
+    /// - 成功時: 最後に評価された値、または制御用の値を含む `Ok`。
     /// - 失敗時: エラー `LoxError` を含む `Err`。
     fn execute_block(
         &mut self,
         statements: Vec<Stmt>,
-        new_env: Environment,
+        new_env: Rc<RefCell<Environment>>,
     ) -> Result<Value, LoxError> {
         let previous_env = std::mem::replace(&mut self.environment, new_env);
         let mut last_result = Value::Nil;
 
         for stmt in statements {
             match self.execute(stmt) {
-                EvalResult::Return(Value::Return(inner_value)) => {
+                EvalResult::Return(
+                    control @ (Value::Return(_) | Value::Break | Value::Continue),
+                ) => {
                     self.environment = previous_env;
-                    return Ok(*inner_value);
+                    return Ok(control);
                 }
                 EvalResult::Return(value) => {
                     last_result = value;
@@ -510,7 +1175,6 @@ impl Evaluator {
                     self.environment = previous_env;
                     return Err(err);
                 }
-                _ => {}
             }
         }
         // ブロック終了後、元の環境を復元
@@ -519,6 +1183,35 @@ impl Evaluator {
         Ok(last_result)
     }
 
+    /// `catch` 節が受け取る値を作成します。
+    ///
+    /// `throw` 文で送出された値はそのまま返し、実行時エラーは `message` と `line`
+    /// フィールドを持つ `Error` クラスのインスタンスに変換します。
+    /// `message` にはエラーの種類の接頭辞を除いた説明文を設定します。
+    ///
+    /// # 引数
+    /// - `err`: 捕捉したエラー。
+    ///
+    /// # 戻り値
+    /// 例外を表す `Value`。発生箇所が不明な場合、`line` は `nil` になります。
+    fn exception_value(&self, err: LoxError) -> Value {
+        if let LoxError::Thrown(value) = err.root() {
+            return value.clone();
+        }
+
+        let mut fields = HashMap::new();
+        fields.insert("message".to_string(), Value::String(err.detail()));
+        fields.insert(
+            "line".to_string(),
+            err.span()
+                .map_or(Value::Nil, |span| Value::Number(span.line as f64)),
+        );
+        Value::Instance {
+            class: Box::new(self.error_class.clone()),
+            fields: Rc::new(RefCell::new(fields)),
+        }
+    }
+
     /// `LiteralValue` を `Value` に変換します。
     ///
     /// # 引数
@@ -561,8 +1254,241 @@ impl Evaluator {
         }
     }
 
+    /// 二項演算子を評価済みの被演算子に適用します。
+    ///
+    /// `Expr::Binary` と複合代入（`+=` など）の両方で使用します。
+    ///
+    /// # 引数
+    /// - `operator`: 適用する演算子のトークン。
+    /// - `left_value`: 左辺の値。
+    /// - `right_value`: 右辺の値。
+    ///
+    /// # 戻り値
+    /// - 成功時: 演算結果を含む `Ok`。
+    /// - 失敗時: 被演算子の型が不正な場合やゼロ除算の場合のエラー `LoxError` を含む `Err`。
+    fn binary_operation(
+        &self,
+        operator: &Token,
+        left_value: Value,
+        right_value: Value,
+    ) -> Result<Value, LoxError> {
+        match operator.token_type {
+            TokenType::Plus => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+                (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be two numbers or two strings for '+'.".to_string(),
+                )),
+            },
+            TokenType::Minus => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l - r)),
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be numbers for '-'.".to_string(),
+                )),
+            },
+            TokenType::Star => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l * r)),
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be numbers for '*'.".to_string(),
+                )),
+            },
+            TokenType::Slash => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => {
+                    if r == 0.0 {
+                        Err(LoxError::DivisionByZero)
+                    } else {
+                        Ok(Value::Number(l / r))
+                    }
+                }
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be numbers for '/'.".to_string(),
+                )),
+            },
+            // `%` と `~/` は床関数に基づく除算で、剰余の符号は除数と一致する
+            // （`a == (a ~/ b) * b + a % b` が成り立つ）
+            TokenType::Percent | TokenType::TildeSlash => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => {
+                    if r == 0.0 {
+                        Err(LoxError::DivisionByZero)
+                    } else if operator.token_type == TokenType::Percent {
+                        Ok(Value::Number(l - r * (l / r).floor()))
+                    } else {
+                        Ok(Value::Number((l / r).floor()))
+                    }
+                }
+                _ => Err(LoxError::InvalidTypeConversion(format!(
+                    "Operands must be numbers for '{}'.",
+                    operator.lexeme
+                ))),
+            },
+            TokenType::StarStar => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l.powf(r))),
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be numbers for '**'.".to_string(),
+                )),
+            },
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => {
+                match (left_value.as_integer(), right_value.as_integer()) {
+                    (Some(l), Some(r)) => match operator.token_type {
+                        TokenType::Ampersand => Ok(Value::Number((l & r) as f64)),
+                        TokenType::Pipe => Ok(Value::Number((l | r) as f64)),
+                        TokenType::Caret => Ok(Value::Number((l ^ r) as f64)),
+                        _ if !(0..64).contains(&r) => Err(LoxError::RuntimeError(format!(
+                            "Shift amount {} is out of range.",
+                            r
+                        ))),
+                        TokenType::LessLess => Ok(Value::Number((l << r) as f64)),
+                        TokenType::GreaterGreater => Ok(Value::Number((l >> r) as f64)),
+                        _ => unreachable!(),
+                    },
+                    _ => Err(LoxError::InvalidTypeConversion(format!(
+                        "Operands must be integers for '{}'.",
+                        operator.lexeme
+                    ))),
+                }
+            }
+            TokenType::EqualEqual => Ok(Value::Boolean(left_value.is_equal(&right_value))),
+            TokenType::BangEqual => Ok(Value::Boolean(!left_value.is_equal(&right_value))),
+            TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual => match (left_value, right_value) {
+                (Value::Number(l), Value::Number(r)) => match operator.token_type {
+                    TokenType::Less => Ok(Value::Boolean(l < r)),
+                    TokenType::LessEqual => Ok(Value::Boolean(l <= r)),
+                    TokenType::Greater => Ok(Value::Boolean(l > r)),
+                    TokenType::GreaterEqual => Ok(Value::Boolean(l >= r)),
+                    _ => unreachable!(),
+                },
+                _ => Err(LoxError::InvalidTypeConversion(
+                    "Operands must be numbers for comparison.".to_string(),
+                )),
+            },
+            _ => Err(LoxError::InvalidTypeConversion(
+                "Invalid binary operator.".to_string(),
+            )),
+        }
+    }
+
+    /// 代入対象の現在の値を読み出し、更新した値を書き戻します。
+    ///
+    /// 複合代入とインクリメントで使用します。対象のオブジェクトや添字の式は一度だけ評価され、
+    /// 値の読み出しと書き込みもそれぞれ一度だけ行われます。
+    ///
+    /// # 引数
+    /// - `target`: 代入対象の式（変数、プロパティ、添字）。
+    /// - `update`: 現在の値から新しい値を計算する関数。
+    ///
+    /// # 戻り値
+    /// - 成功時: 更新前の値と更新後の値の組を含む `Ok`。
+    /// - 失敗時: エラー `LoxError` を含む `Err`。
+    fn update_target(
+        &mut self,
+        target: &Expr,
+        update: impl FnOnce(&mut Self, Value) -> Result<Value, LoxError>,
+    ) -> Result<(Value, Value), LoxError> {
+        match target {
+            Expr::Variable { name, depth } => {
+                let current = match depth {
+                    Some(distance) => {
+                        Environment::get_at(&self.environment, *distance, &name.lexeme)
+                    }
+                    None => self.globals.borrow().get(&name.lexeme),
+                }
+                .ok_or_else(|| LoxError::UndefinedVariable(name.lexeme.clone()))?;
+                let new_value = update(self, current.clone())?;
+                match depth {
+                    Some(distance) => Environment::assign_at(
+                        &self.environment,
+                        *distance,
+                        name.lexeme.clone(),
+                        new_value.clone(),
+                    ),
+                    None => self
+                        .globals
+                        .borrow_mut()
+                        .assign(name.lexeme.clone(), new_value.clone()),
+                }
+                .map_err(|_| LoxError::UndefinedVariable(name.lexeme.clone()))?;
+                Ok((current, new_value))
+            }
+            Expr::Get { object, name } => match self.evaluate(object)? {
+                Value::Instance { fields, .. } => {
+                    let current = fields.borrow().get(&name.lexeme).cloned().ok_or_else(|| {
+                        LoxError::RuntimeError(format!("Undefined property '{}'.", name.lexeme))
+                    })?;
+                    let new_value = update(self, current.clone())?;
+                    fields
+                        .borrow_mut()
+                        .insert(name.lexeme.clone(), new_value.clone());
+                    Ok((current, new_value))
+                }
+                _ => Err(LoxError::RuntimeError(
+                    "Only instances have fields.".to_string(),
+                )),
+            },
+            Expr::Index { object, index, .. } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                match object {
+                    Value::List(elements) => {
+                        let position = index.as_index(elements.borrow().len())?;
+                        let current = elements.borrow()[position].clone();
+                        let new_value = update(self, current.clone())?;
+                        // 更新関数の中でリストが縮んだ場合に備えて、書き込み前に添字を再検証する
+                        let mut elements = elements.borrow_mut();
+                        let position = index.as_index(elements.len())?;
+                        elements[position] = new_value.clone();
+                        Ok((current, new_value))
+                    }
+                    Value::Map(entries) => {
+                        let key = MapKey::from_value(&index)?;
+                        let current = entries.borrow().get(&key).cloned().ok_or_else(|| {
+                            LoxError::RuntimeError(format!("Undefined key '{}'.", index))
+                        })?;
+                        let new_value = update(self, current.clone())?;
+                        entries.borrow_mut().insert(key, new_value.clone());
+                        Ok((current, new_value))
+                    }
+                    _ => Err(LoxError::RuntimeError(
+                        "Only lists and maps can be indexed.".to_string(),
+                    )),
+                }
+            }
+            _ => Err(LoxError::RuntimeError(
+                "Invalid assignment target.".to_string(),
+            )),
+        }
+    }
+
+    /// 条件式を評価し、分岐やループの継続に使う真偽を返します。
+    ///
+    /// # 引数
+    /// - `condition`: 評価する条件式。
+    ///
+    /// # 戻り値
+    /// - 成功時: 条件の真偽を含む `Ok`。
+    /// - 失敗時: 評価中のエラー、または厳格モードで真偽値以外に評価された場合の
+    ///   `LoxError::NonBooleanCondition` を含む `Err`。
+    fn evaluate_condition(&mut self, condition: &Expr) -> Result<bool, LoxError> {
+        match self.evaluate(condition)? {
+            Value::Boolean(b) => Ok(b),
+            _ if self.strict_conditions => Err(Self::locate(
+                LoxError::NonBooleanCondition("Condition must evaluate to a boolean.".to_string()),
+                condition,
+            )),
+            value => Ok(self.is_truthy(value)),
+        }
+    }
+
     /// 関数呼び出しを評価します。
     ///
+    /// 関数、束縛メソッド、クラス（インスタンスの生成）を呼び出すことができます。
+    ///
     /// # 引数
     /// - `function`: 呼び出される関数の値。
     /// - `arguments`: 関数に渡される引数。
@@ -571,31 +1497,236 @@ impl Evaluator {
     /// - 成功時: 評価結果 `Value` を含む `Ok`。
     /// - 失敗時: エラー `LoxError` を含む `Err`。
     fn evaluate_call(&mut self, function: Value, arguments: Vec<Value>) -> Result<Value, LoxError> {
-        // 関数として扱えるかを確認
-        if let Value::Function { params, body, .. } = function {
-            // 引数の数を検証
-            if params.len() != arguments.len() {
-                return Err(LoxError::InvalidTypeConversion(format!(
+        match function {
+            Value::Function {
+                params,
+                body,
+                closure,
+                ..
+            } => self.call_function(params, body, closure, arguments, None),
+            Value::BoundMethod { receiver, method } => match *method {
+                Value::Function {
+                    name,
+                    params,
+                    body,
+                    closure,
+                } => {
+                    let result = self.call_function(
+                        params,
+                        body,
+                        closure,
+                        arguments,
+                        Some((*receiver).clone()),
+                    )?;
+                    // 初期化メソッドは常にインスタンス自身を返す
+                    if name == "init" {
+                        Ok(*receiver)
+                    } else {
+                        Ok(result)
+                    }
+                }
+                _ => Err(LoxError::RuntimeError(
+                    "Bound method must be a function.".to_string(),
+                )),
+            },
+            Value::Class { .. } => self.instantiate(function, arguments),
+            Value::NativeFunction(function) => function(arguments),
+            _ => Err(LoxError::InvalidTypeConversion(
+                "Can only call functions.".to_string(),
+            )),
+        }
+    }
+
+    /// 呼び出される値が受け取る引数の数を検証します。
+    ///
+    /// デフォルト値を持つパラメータは省略でき、可変長パラメータは上限を持ちません。
+    /// クラスは `init` メソッドのパラメータで検証します。ネイティブ関数は各関数の中で検証します。
+    ///
+    /// # 引数
+    /// - `function`: 呼び出される値。
+    /// - `count`: 渡された引数の数。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: 引数の数が一致しない場合の `LoxError`。
+    fn check_arity(function: &Value, count: usize) -> Result<(), LoxError> {
+        match function {
+            Value::Function { params, .. } => {
+                let required = params
+                    .iter()
+                    .filter(|param| param.default.is_none() && !param.rest)
+                    .count();
+                let variadic = params.last().is_some_and(|param| param.rest);
+                if count >= required && (variadic || count <= params.len()) {
+                    return Ok(());
+                }
+                let expected = if variadic {
+                    format!("at least {}", required)
+                } else if required == params.len() {
+                    required.to_string()
+                } else {
+                    format!("{} to {}", required, params.len())
+                };
+                Err(LoxError::InvalidTypeConversion(format!(
                     "Expected {} arguments but got {}.",
-                    params.len(),
-                    arguments.len()
-                )));
+                    expected, count
+                )))
+            }
+            Value::BoundMethod { method, .. } => Self::check_arity(method, count),
+            Value::Class { .. } => match function.find_method("init") {
+                Some(initializer) => Self::check_arity(&initializer, count),
+                None if count == 0 => Ok(()),
+                None => Err(LoxError::RuntimeError(format!(
+                    "Expected 0 arguments but got {}.",
+                    count
+                ))),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// コールスタックに表示する、呼び出される関数の名前を返します。
+    ///
+    /// # 引数
+    /// - `function`: 呼び出される値。
+    /// - `callee`: 呼び出し対象の式。名前を持たないネイティブ関数では式の名前を使用します。
+    ///
+    /// # 戻り値
+    /// 関数の名前。メソッドの場合は `クラス名.メソッド名`。
+    fn frame_name(function: &Value, callee: &Expr) -> String {
+        match function {
+            Value::Function { name, .. } | Value::Class { name, .. } => name.clone(),
+            Value::BoundMethod { receiver, method } => {
+                let method = match method.as_ref() {
+                    Value::Function { name, .. } => name.as_str(),
+                    _ => "<method>",
+                };
+                match receiver.as_ref() {
+                    Value::Instance { class, .. } => match class.as_ref() {
+                        Value::Class { name, .. } => format!("{}.{}", name, method),
+                        _ => method.to_string(),
+                    },
+                    _ => method.to_string(),
+                }
             }
-            // 新しい環境を作成し、引数をバインド
-            let mut new_env = Environment::with_enclosing(self.environment.clone());
-            for (param, arg) in params.iter().zip(arguments.iter()) {
-                new_env.define(param.lexeme.clone(), arg.clone());
+            _ => match callee {
+                Expr::Variable { name, .. } => name.lexeme.clone(),
+                _ => "<native fn>".to_string(),
+            },
+        }
+    }
+
+    /// 呼び出しの引数を評価します。スプレッド引数はリストの要素を個別の引数として展開します。
+    ///
+    /// # 引数
+    /// - `arguments`: 引数の式のリスト。
+    ///
+    /// # 戻り値
+    /// - 成功時: 評価された引数のリストを含む `Ok`。
+    /// - 失敗時: スプレッドの対象がリストでない場合などのエラー `LoxError` を含む `Err`。
+    fn evaluate_arguments(&mut self, arguments: &[Expr]) -> Result<Vec<Value>, LoxError> {
+        let mut values = Vec::new();
+        for argument in arguments {
+            match argument {
+                Expr::Spread { expression, .. } => match self.evaluate(expression)? {
+                    Value::List(elements) => values.extend(elements.borrow().iter().cloned()),
+                    _ => {
+                        return Err(LoxError::RuntimeError(
+                            "Only lists can be spread into arguments.".to_string(),
+                        ))
+                    }
+                },
+                _ => values.push(self.evaluate(argument)?),
             }
-            // 関数のブロックを実行
-            match self.execute_block(body, new_env) {
-                Ok(Value::Return(value)) => Ok(*value),
-                Ok(value) => Ok(value),
-                Err(err) => Err(err), // ここで既に LoxError を返しているのでそのまま渡す
+        }
+        Ok(values)
+    }
+
+    /// ユーザー定義関数の本体を実行します。
+    ///
+    /// # 引数
+    /// - `params`: 関数のパラメータリスト。省略された引数にはデフォルト値を呼び出し時に評価して束縛します。
+    /// - `body`: 関数の本体。
+    /// - `closure`: 関数が定義された環境。呼び出し時の環境はこれを囲む新しいスコープになります。
+    /// - `arguments`: 関数に渡される引数。
+    /// - `this`: メソッド呼び出しの場合、`this` に束縛するインスタンス。
+    ///
+    /// # 戻り値
+    /// - 成功時: 関数の返り値を含む `Ok`。
+    /// - 失敗時: エラー `LoxError` を含む `Err`。
+    fn call_function(
+        &mut self,
+        params: Vec<Parameter>,
+        body: Rc<Vec<Stmt>>,
+        closure: Rc<RefCell<Environment>>,
+        arguments: Vec<Value>,
+        this: Option<Value>,
+    ) -> Result<Value, LoxError> {
+        // 引数の数は呼び出し前に `check_arity` で検証済み
+        // 新しい環境を作成し、引数をバインド
+        let new_env = Rc::new(RefCell::new(Environment::with_enclosing(closure)));
+        if let Some(instance) = this {
+            new_env.borrow_mut().define("this".to_string(), instance);
+        }
+        let mut arguments = arguments.into_iter();
+        for param in &params {
+            if param.rest {
+                // 残りの引数をすべてリストとして束縛する
+                let rest = Value::List(Rc::new(RefCell::new(arguments.by_ref().collect())));
+                new_env.borrow_mut().define(param.name.lexeme.clone(), rest);
+                continue;
             }
-        } else {
-            Err(LoxError::InvalidTypeConversion(
-                "Can only call functions.".to_string(),
-            ))
+            let value = match (arguments.next(), &param.default) {
+                (Some(arg), _) => arg,
+                // デフォルト値は、それより前の引数が束縛された呼び出し先のスコープで評価する
+                (None, Some(default)) => {
+                    let previous_env =
+                        std::mem::replace(&mut self.environment, Rc::clone(&new_env));
+                    let value = self.evaluate(default);
+                    self.environment = previous_env;
+                    value?
+                }
+                (None, None) => unreachable!("arity is checked before binding"),
+            };
+            new_env
+                .borrow_mut()
+                .define(param.name.lexeme.clone(), value);
+        }
+        // 関数のブロックを実行
+        match self.execute_block(body.as_ref().clone(), new_env) {
+            Ok(Value::Return(value)) => Ok(*value),
+            Ok(value) => Ok(value),
+            Err(err) => Err(err), // ここで既に LoxError を返しているのでそのまま渡す
+        }
+    }
+
+    /// クラスを呼び出して新しいインスタンスを生成します。
+    ///
+    /// クラスに `init` メソッドが定義されている場合は、生成したインスタンスに束縛して引数とともに呼び出します。
+    ///
+    /// # 引数
+    /// - `class`: インスタンス化するクラス。
+    /// - `arguments`: 初期化メソッドに渡される引数。
+    ///
+    /// # 戻り値
+    /// - 成功時: 生成されたインスタンスを含む `Ok`。
+    /// - 失敗時: エラー `LoxError` を含む `Err`。
+    fn instantiate(&mut self, class: Value, arguments: Vec<Value>) -> Result<Value, LoxError> {
+        let initializer = class.find_method("init");
+        let instance = Value::Instance {
+            class: Box::new(class),
+            fields: Rc::new(RefCell::new(HashMap::new())),
+        };
+
+        match initializer {
+            Some(method) => self.evaluate_call(
+                Value::BoundMethod {
+                    receiver: Box::new(instance),
+                    method: Box::new(method),
+                },
+                arguments,
+            ),
+            None => Ok(instance),
         }
     }
 
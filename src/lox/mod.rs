@@ -1,8 +1,11 @@
 pub mod ast;
+pub mod diagnostic;
 pub mod error;
 pub mod evaluator;
+pub mod native;
 pub mod parser;
 pub mod printer;
+pub mod resolver;
 pub mod scanner;
 pub mod token;
 pub mod token_type;
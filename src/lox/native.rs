@@ -0,0 +1,148 @@
+use crate::lox::error::LoxError;
+use crate::lox::evaluator::{MapKey, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// ネイティブ関数に渡された引数の数を検証します。
+///
+/// # 引数
+/// - `name`: ネイティブ関数の名前（エラーメッセージに使用）。
+/// - `args`: 渡された引数。
+/// - `expected`: 期待する引数の数。
+///
+/// # 戻り値
+/// - 成功時: `Ok(())`。
+/// - 失敗時: 引数の数が一致しない場合の `LoxError::RuntimeError`。
+fn check_arity(name: &str, args: &[Value], expected: usize) -> Result<(), LoxError> {
+    if args.len() != expected {
+        return Err(LoxError::RuntimeError(format!(
+            "{}() expected {} arguments but got {}.",
+            name,
+            expected,
+            args.len()
+        )));
+    }
+    Ok(())
+}
+
+/// 引数をリストとして取り出します。
+///
+/// # 引数
+/// - `name`: ネイティブ関数の名前（エラーメッセージに使用）。
+/// - `value`: 対象の値。
+///
+/// # 戻り値
+/// - 成功時: リストの要素への共有参照。
+/// - 失敗時: 値がリストでない場合の `LoxError::RuntimeError`。
+fn expect_list(name: &str, value: &Value) -> Result<Rc<RefCell<Vec<Value>>>, LoxError> {
+    match value {
+        Value::List(elements) => Ok(Rc::clone(elements)),
+        _ => Err(LoxError::RuntimeError(format!(
+            "{}() expects a list as its first argument.",
+            name
+        ))),
+    }
+}
+
+/// 引数をマップとして取り出します。
+///
+/// # 引数
+/// - `name`: ネイティブ関数の名前（エラーメッセージに使用）。
+/// - `value`: 対象の値。
+///
+/// # 戻り値
+/// - 成功時: マップのエントリへの共有参照。
+/// - 失敗時: 値がマップでない場合の `LoxError::RuntimeError`。
+fn expect_map(name: &str, value: &Value) -> Result<Rc<RefCell<BTreeMap<MapKey, Value>>>, LoxError> {
+    match value {
+        Value::Map(entries) => Ok(Rc::clone(entries)),
+        _ => Err(LoxError::RuntimeError(format!(
+            "{}() expects a map as its first argument.",
+            name
+        ))),
+    }
+}
+
+/// `len(value)`: リストの要素数、マップのエントリ数、または文字列の文字数を返します。
+pub fn len(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("len", &args, 1)?;
+    match &args[0] {
+        Value::List(elements) => Ok(Value::Number(elements.borrow().len() as f64)),
+        Value::Map(entries) => Ok(Value::Number(entries.borrow().len() as f64)),
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        _ => Err(LoxError::RuntimeError(
+            "len() expects a list, a map or a string.".to_string(),
+        )),
+    }
+}
+
+/// `push(list, value)`: リストの末尾に要素を追加し、追加後の要素数を返します。
+pub fn push(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("push", &args, 2)?;
+    let list = expect_list("push", &args[0])?;
+    let mut elements = list.borrow_mut();
+    elements.push(args[1].clone());
+    Ok(Value::Number(elements.len() as f64))
+}
+
+/// `pop(list)`: リストの末尾の要素を取り除いて返します。
+pub fn pop(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("pop", &args, 1)?;
+    let list = expect_list("pop", &args[0])?;
+    let popped = list.borrow_mut().pop();
+    popped.ok_or_else(|| LoxError::RuntimeError("Can't pop from an empty list.".to_string()))
+}
+
+/// `slice(list, start, end)`: `start` 以上 `end` 未満の要素を持つ新しいリストを返します。
+pub fn slice(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("slice", &args, 3)?;
+    let list = expect_list("slice", &args[0])?;
+    let elements = list.borrow();
+    // `end` は要素数と等しくてもよいため、範囲の上限を 1 つ広げて検証する
+    let start = args[1].as_index(elements.len() + 1)?;
+    let end = args[2].as_index(elements.len() + 1)?;
+    if start > end {
+        return Err(LoxError::RuntimeError(format!(
+            "Slice start {} is greater than end {}.",
+            start, end
+        )));
+    }
+    Ok(Value::List(Rc::new(RefCell::new(
+        elements[start..end].to_vec(),
+    ))))
+}
+
+/// `keys(map)`: マップのキーをキーの順に並べたリストを返します。
+pub fn keys(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("keys", &args, 1)?;
+    let map = expect_map("keys", &args[0])?;
+    let keys = map.borrow().keys().map(MapKey::to_value).collect();
+    Ok(Value::List(Rc::new(RefCell::new(keys))))
+}
+
+/// `values(map)`: マップの値をキーの順に並べたリストを返します。
+pub fn values(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("values", &args, 1)?;
+    let map = expect_map("values", &args[0])?;
+    let values = map.borrow().values().cloned().collect();
+    Ok(Value::List(Rc::new(RefCell::new(values))))
+}
+
+/// `has(map, key)`: マップがキーを含むかどうかを返します。
+pub fn has(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("has", &args, 2)?;
+    let map = expect_map("has", &args[0])?;
+    let key = MapKey::from_value(&args[1])?;
+    let found = map.borrow().contains_key(&key);
+    Ok(Value::Boolean(found))
+}
+
+/// `remove(map, key)`: マップからキーを取り除き、取り除いた値（存在しない場合は `nil`）を返します。
+pub fn remove(args: Vec<Value>) -> Result<Value, LoxError> {
+    check_arity("remove", &args, 2)?;
+    let map = expect_map("remove", &args[0])?;
+    let key = MapKey::from_value(&args[1])?;
+    let removed = map.borrow_mut().remove(&key);
+    Ok(removed.unwrap_or(Value::Nil))
+}
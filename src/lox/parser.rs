@@ -1,4 +1,4 @@
-use crate::lox::ast::{Expr, Stmt};
+use crate::lox::ast::{Expr, Parameter, Stmt};
 use crate::lox::error::LoxError;
 use crate::lox::token::Token;
 use crate::lox::token_type::{LiteralValue, TokenType};
@@ -13,11 +13,16 @@ const MAX_RECURSION_DEPTH: usize = 1000;
 /// - `tokens`: 解析対象となるトークンのリスト。
 /// - `current`: 現在解析中のトークンのインデックス。
 /// - `recursion_depth`: 再帰の深さを追跡し、スタックオーバーフローを防止する。
+/// - `loop_depth`: 解析中のループのネストの深さ。`break` / `continue` の検証に使用する。
+/// - `block_depth`: 解析中のブロックのネストの深さ。エラーからの回復に使用する。
+/// - `errors`: 解析中に見つかったエラーのリスト。
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     recursion_depth: usize,
-    in_function: bool,
+    loop_depth: usize,
+    block_depth: usize,
+    errors: Vec<LoxError>,
 }
 
 impl Parser {
@@ -33,7 +38,9 @@ impl Parser {
             tokens,
             current: 0,
             recursion_depth: 0,
-            in_function: false,
+            loop_depth: 0,
+            block_depth: 0,
+            errors: Vec::new(),
         }
     }
 
@@ -41,16 +48,66 @@ impl Parser {
     ///
     /// # 戻り値
     /// - 成功時: ステートメントのリスト。
-    /// - 失敗時: `LoxError`。
+    /// - 失敗時: 発生箇所を持つ `LoxError`。複数のエラーが見つかった場合は、
+    ///   それらをまとめた `LoxError::Multiple`。
     pub fn parse(&mut self) -> Result<Vec<Stmt>, LoxError> {
+        let (statements, mut errors) = self.parse_with_recovery();
+        match errors.len() {
+            0 => Ok(statements),
+            1 => Err(errors.remove(0)),
+            _ => Err(LoxError::Multiple(errors)),
+        }
+    }
+
+    /// エラーから回復しながらトークンのリストを最後まで解析します。
+    ///
+    /// 宣言ごとにエラーから回復するため、ブロックや関数本体の中のエラーもその文だけを読み飛ばし、
+    /// 次の文の境界（`;` の直後、または `class`、`fun`、`var`、`for`、`if`、`while`、`print`、
+    /// `return`、`throw`、`try` の直前、ブロックの中ではブロックを閉じる `}` の直前）から解析を続けます。
+    /// エラーのあった文を除いた部分的な構文木は、エディタなどのツールで利用できます。
+    ///
+    /// # 戻り値
+    /// 解析できたステートメントのリストと、発生箇所を持つエラーのリストの組。
+    pub fn parse_with_recovery(&mut self) -> (Vec<Stmt>, Vec<LoxError>) {
         let mut statements = Vec::new();
         while !self.is_at_end() {
-            match self.declaration() {
-                Ok(stmt) => statements.push(stmt),
-                Err(err) => return Err(err),
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
+        }
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    /// エラーの後、次の文の境界までトークンを読み飛ばします（パニックモードによる回復）。
+    ///
+    /// ブロックの中では、ブロックを閉じる `}` を読み飛ばさずに残し、ブロックの解析を続けられるようにします。
+    fn synchronize(&mut self) {
+        if self.block_depth == 0 || !self.check(TokenType::RightBrace) {
+            self.advance();
+        }
+
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+            if let Some(token) = self.peek() {
+                match token.token_type {
+                    TokenType::Class
+                    | TokenType::Fun
+                    | TokenType::Var
+                    | TokenType::For
+                    | TokenType::If
+                    | TokenType::While
+                    | TokenType::Print
+                    | TokenType::Return
+                    | TokenType::Throw
+                    | TokenType::Try => return,
+                    TokenType::RightBrace if self.block_depth > 0 => return,
+                    _ => {}
+                }
             }
+            self.advance();
         }
-        Ok(statements)
     }
 
     /// トークンを解析し、ステートメントを生成します。
@@ -61,6 +118,8 @@ impl Parser {
     /// - `while` 文
     /// - `if` 文
     /// - `return` 文
+    /// - `break` 文 / `continue` 文
+    /// - `throw` 文 / `try` 文
     /// - `print` 文
     /// - ブロック `{ ... }`
     /// - 単一の式
@@ -90,6 +149,18 @@ impl Parser {
                 self.advance();
                 self.return_statement()
             }
+            TokenType::Break | TokenType::Continue => {
+                self.advance();
+                self.loop_control_statement()
+            }
+            TokenType::Throw => {
+                self.advance();
+                self.throw_statement()
+            }
+            TokenType::Try => {
+                self.advance();
+                self.try_statement()
+            }
             TokenType::Print => {
                 self.advance();
                 self.print_statement()
@@ -109,13 +180,35 @@ impl Parser {
         }
     }
 
+    /// 宣言を1つ解析し、エラーが発生した場合は記録して次の文の境界まで読み飛ばします。
+    ///
+    /// # 戻り値
+    /// - 成功時: ステートメントを含む `Some`。
+    /// - 失敗時: `None`。エラーは発生箇所を付与して `errors` に追加されます。
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.try_declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                // 発生箇所が特定されていないエラーは現在のトークンの位置とする
+                let err = self.locate(err);
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
     /// トークンを解析して、ステートメントを生成します。
     ///
     /// # 戻り値
     /// - 成功時: ステートメント。
     /// - 失敗時: `LoxError`。
-    fn declaration(&mut self) -> Result<Stmt, LoxError> {
-        if self.match_token(&[TokenType::Fun]) {
+    fn try_declaration(&mut self) -> Result<Stmt, LoxError> {
+        if self.match_token(&[TokenType::Class]) {
+            self.class_declaration()
+        } else if self.check(TokenType::Fun) && !self.check_next(TokenType::LeftParen) {
+            // `fun (` で始まる場合は無名関数の式文として扱う
+            self.advance();
             self.function("function")
         } else if self.match_token(&[TokenType::Var]) {
             self.var_declaration()
@@ -124,6 +217,61 @@ impl Parser {
         }
     }
 
+    /// クラス宣言を解析し、対応するステートメントを生成します。
+    ///
+    /// 例: `class Point { init(x, y) { this.x = x; this.y = y; } }`、`class B < A { ... }`
+    ///
+    /// # 処理の流れ
+    /// 1. クラス名を取得します。
+    /// 2. `<` が続く場合はスーパークラス名を取得します。自分自身の継承はエラーになります。
+    /// 3. `{` の後に続くメソッド定義を `}` が現れるまで解析します。
+    /// 4. `}` の存在を確認してクラス本体の終わりを検証します。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Stmt::Class` 型のクラス宣言ステートメント。
+    /// - 失敗時: `LoxError`。
+    fn class_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let name = self
+            .consume(TokenType::Identifier, "Expect class name.")?
+            .clone();
+
+        let superclass = if self.match_token(&[TokenType::Less]) {
+            let superclass_name = self
+                .consume(TokenType::Identifier, "Expect superclass name.")?
+                .clone();
+            if superclass_name.lexeme == name.lexeme {
+                return Err(Self::error(
+                    &superclass_name,
+                    "A class can't inherit from itself.",
+                ));
+            }
+            Some(Expr::Variable {
+                name: superclass_name,
+                depth: None,
+            })
+        } else {
+            None
+        };
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let method = self.function("method")?;
+            if let Stmt::Function { name, .. } = &method {
+                methods.push((name.clone(), method));
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+        })
+    }
+
     /// 変数宣言を解析し、対応するステートメントを生成します。
     ///
     /// 例: `var x = 10;` のようなコードを解析します。
@@ -172,7 +320,7 @@ impl Parser {
     ///
     /// # 処理の流れ
     /// 1. 初期化式（`initializer`）を解析します。`var` 宣言または式文が許容されます。
-    /// 2. 条件式（`condition`）を解析します。省略された場合、常に `true` を条件とします。
+    /// 2. 条件式（`condition`）を解析します。省略された場合は `None` とし、常に真として扱われます。
     /// 3. 増分式（`increment`）を解析します。
     /// 4. ループの本体（`body`）を解析します。
     ///
@@ -180,10 +328,8 @@ impl Parser {
     /// - 成功時: `Stmt::For` 型のステートメント。
     /// - 失敗時: `LoxError`。
     fn for_statement(&mut self) -> Result<Stmt, LoxError> {
-        self.consume(
-            TokenType::Semicolon,
-            "Expected ';' after expression or assignment.",
-        )?;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+
         // 初期化式の解析
         let initializer = if self.match_token(&[TokenType::Semicolon]) {
             None
@@ -195,11 +341,9 @@ impl Parser {
 
         // 条件式の解析
         let condition = if !self.check(TokenType::Semicolon) {
-            self.expression()?
+            Some(self.expression()?)
         } else {
-            Expr::Literal {
-                value: LiteralValue::Boolean(true),
-            }
+            None
         };
 
         self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
@@ -214,22 +358,15 @@ impl Parser {
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
         // ループ本体の解析
-        let mut body = self.statement()?;
-
-        // 増分式をループ本体の末尾に追加
-        if let Some(increment) = increment {
-            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
-        }
-
-        // 条件式と本体を `while` 文としてラップ
-        let body = Stmt::While(condition, Box::new(body));
-
-        // 初期化式を全体の前に追加
-        if let Some(initializer) = initializer {
-            Ok(Stmt::Block(vec![*initializer, body]))
-        } else {
-            Ok(body)
-        }
+        // 増分式は `continue` でも実行されるよう、本体に埋め込まず `Stmt::For` に保持する
+        let body = self.loop_body()?;
+
+        Ok(Stmt::For {
+            initializer,
+            condition,
+            increment,
+            body: Box::new(body),
+        })
     }
 
     /// `while` 文を解析し、対応するステートメントを生成します。
@@ -251,11 +388,58 @@ impl Parser {
 
         self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
 
-        let body = self.statement()?;
+        let body = self.loop_body()?;
 
         Ok(Stmt::While(condition, Box::new(body)))
     }
 
+    /// ループの本体を解析します。
+    ///
+    /// 本体の解析中は `loop_depth` を増やし、`break` と `continue` の使用を許可します。
+    ///
+    /// # 戻り値
+    /// - 成功時: ループ本体のステートメント。
+    /// - 失敗時: `LoxError`。
+    fn loop_body(&mut self) -> Result<Stmt, LoxError> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    /// `break` 文または `continue` 文を解析し、対応するステートメントを生成します。
+    ///
+    /// 例: `break;` または `continue;`
+    ///
+    /// # 処理の流れ
+    /// 1. ループの外側で使用されている場合はエラーを返します。
+    /// 2. `;` の存在を確認し、ステートメントの終わりを検証します。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Stmt::Break` または `Stmt::Continue` 型のステートメント。
+    /// - 失敗時: `LoxError`。
+    fn loop_control_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+
+        if self.loop_depth == 0 {
+            return Err(Self::error(
+                &keyword,
+                &format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+            ));
+        }
+
+        self.consume(
+            TokenType::Semicolon,
+            &format!("Expect ';' after '{}'.", keyword.lexeme),
+        )?;
+
+        if keyword.token_type == TokenType::Break {
+            Ok(Stmt::Break { keyword })
+        } else {
+            Ok(Stmt::Continue { keyword })
+        }
+    }
+
     /// `if` 文を解析し、対応するステートメントを生成します。
     ///
     /// 例: `if (condition) { ... } else { ... }`
@@ -303,12 +487,10 @@ impl Parser {
     /// # 戻り値
     /// - 成功時: `Stmt::Return` 型のステートメント。
     /// - 失敗時: `LoxError`。
+    ///
+    /// # 備考
+    /// 関数外での `return` の検出は `Resolver` が行います。
     fn return_statement(&mut self) -> Result<Stmt, LoxError> {
-        // 関数内かどうかをチェック
-        if !self.in_function {
-            return Err(LoxError::ReturnOutsideFunction);
-        }
-
         let keyword = self.previous().clone();
 
         let value = if !self.check(TokenType::Semicolon) {
@@ -322,6 +504,71 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    /// `throw` 文を解析し、対応するステートメントを生成します。
+    ///
+    /// 例: `throw "invalid input";`
+    ///
+    /// # 戻り値
+    /// - 成功時: `Stmt::Throw` 型のステートメント。
+    /// - 失敗時: `LoxError`。
+    fn throw_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+
+        self.consume(TokenType::Semicolon, "Expected ';' after thrown value.")?;
+
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    /// `try` 文を解析し、対応するステートメントを生成します。
+    ///
+    /// 例: `try { ... } catch (e) { ... } finally { ... }`
+    ///
+    /// # 処理の流れ
+    /// 1. `try` の後のブロックを解析します。
+    /// 2. `catch` があれば、`(` 例外を受け取る変数名 `)` とブロックを解析します。
+    /// 3. `finally` があれば、そのブロックを解析します。
+    /// 4. `catch` と `finally` のどちらもない場合はエラーとします。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Stmt::Try` 型のステートメント。
+    /// - 失敗時: `LoxError`。
+    fn try_statement(&mut self) -> Result<Stmt, LoxError> {
+        let body = self.block_body("Expect '{' after 'try'.")?;
+
+        let catch = if self.match_token(&[TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self
+                .consume(TokenType::Identifier, "Expect exception variable name.")?
+                .clone();
+            self.consume(
+                TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            Some((name, self.block_body("Expect '{' before catch body.")?))
+        } else {
+            None
+        };
+
+        let finally = if self.match_token(&[TokenType::Finally]) {
+            Some(self.block_body("Expect '{' after 'finally'.")?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.locate(LoxError::ParseError(
+                "Expect 'catch' or 'finally' after try block.".to_string(),
+            )));
+        }
+
+        Ok(Stmt::Try {
+            body,
+            catch,
+            finally,
+        })
+    }
+
     /// `print` 文を解析し、対応するステートメントを生成します。
     ///
     /// 例: `print value;`
@@ -350,53 +597,213 @@ impl Parser {
     /// 2. `}` が現れるまで繰り返します。
     /// 3. `}` の存在を確認してブロックの終わりを検証します。
     ///
+    /// ブロック内の文で発生したエラーは記録され、その文を除いてブロックの解析を続けます。
+    ///
     /// # 戻り値
     /// - 成功時: `Stmt::Block` 型のステートメント。
     /// - 失敗時: `LoxError`。
     fn block(&mut self) -> Result<Stmt, LoxError> {
         let mut statements = Vec::new();
 
+        self.block_depth += 1;
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
+        self.block_depth -= 1;
 
         self.consume(TokenType::RightBrace, "Expected '}' after block.")?;
 
         Ok(Stmt::Block(statements))
     }
 
+    /// `{` を確認した後にブロックを解析し、そのステートメントのリストを返します。
+    ///
+    /// # 引数
+    /// - `message`: `{` がない場合のエラーメッセージ。
+    ///
+    /// # 戻り値
+    /// - 成功時: ブロック内のステートメントのリスト。
+    /// - 失敗時: `LoxError`。
+    fn block_body(&mut self, message: &str) -> Result<Vec<Stmt>, LoxError> {
+        self.consume(TokenType::LeftBrace, message)?;
+        match self.block()? {
+            Stmt::Block(statements) => Ok(statements),
+            _ => Err(LoxError::ParseError("Expected a block.".to_string())),
+        }
+    }
+
     /// 代入式を解析し、対応する `Expr` を生成します。
     ///
-    /// 例: `a = b`
+    /// 例: `a = b`、`a += 1`
     ///
     /// # 処理の流れ
-    /// 1. 等価性の解析を行います。
-    /// 2. `=` が現れた場合、右辺の式を解析します。
-    /// 3. 代入対象が変数でない場合、エラーを返します。
+    /// 1. 条件式の解析を行います。
+    /// 2. `=` または複合代入演算子が現れた場合、右辺の式を解析します。
+    /// 3. 代入対象が変数、プロパティ、添字でない場合、エラーを返します。
     ///
     /// # 戻り値
-    /// - 成功時: `Expr::Assign` またはその代わりの式。
+    /// - 成功時: `Expr::Assign`、`Expr::Set`、`Expr::SetIndex`、`Expr::CompoundAssign`
+    ///   またはその代わりの式。
     /// - 失敗時: `LoxError`。
     fn assignment(&mut self) -> Result<Expr, LoxError> {
-        let mut expr = self.equality()?;
+        let expr = self.conditional()?;
 
         if self.match_token(&[TokenType::Equal]) {
+            let equals = self.previous().clone();
             let value = self.assignment()?;
-            if let Expr::Variable { name } = expr {
-                return Ok(Expr::Assign {
+            return match expr {
+                Expr::Variable { name, .. } => Ok(Expr::Assign {
                     name,
                     value: Box::new(value),
-                });
-            } else {
-                return Err(LoxError::ParseError(
-                    "Invalid assignment target.".to_string(),
-                ));
-            }
+                    depth: None,
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                }),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                }),
+                _ => Err(Self::error(&equals, "Invalid assignment target.")),
+            };
+        }
+
+        if self.match_token(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+            return Ok(Expr::CompoundAssign {
+                target: Box::new(Self::update_target(expr, &operator)?),
+                operator,
+                value: Box::new(value),
+            });
         }
 
         Ok(expr)
     }
 
+    /// 条件式を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `n == 1 ? "item" : "items"`
+    ///
+    /// 条件式は右結合で、`a ? b : c ? d : e` は `a ? b : (c ? d : e)` と解釈されます。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Conditional` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn conditional(&mut self) -> Result<Expr, LoxError> {
+        let condition = self.or()?;
+
+        if self.match_token(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
+
+        Ok(condition)
+    }
+
+    /// 文字列補間を解析し、`Expr::Interpolation` を生成します。
+    ///
+    /// 呼び出し時点で最初の `StringInterpolation` トークンは消費済みです。
+    /// 文字列部分と埋め込み式が交互に続き、最後の文字列部分は `StringLit` トークンになります。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Interpolation`。
+    /// - 失敗時: `LoxError`。
+    fn interpolation(&mut self) -> Result<Expr, LoxError> {
+        let mut parts = Vec::new();
+
+        loop {
+            if let Some(LiteralValue::String(segment)) = &self.previous().literal {
+                if !segment.is_empty() {
+                    parts.push(Expr::Literal {
+                        value: LiteralValue::String(segment.clone()),
+                        token: self.previous().clone(),
+                    });
+                }
+            }
+
+            if self.previous().token_type == TokenType::StringLit {
+                return Ok(Expr::Interpolation { parts });
+            }
+
+            parts.push(self.expression()?);
+
+            if !self.match_token(&[TokenType::StringInterpolation]) {
+                self.consume(
+                    TokenType::StringLit,
+                    "Expect '}' after interpolated expression.",
+                )?;
+            }
+        }
+    }
+
+    /// 複合代入やインクリメントの対象となる式を検証します。
+    ///
+    /// # 引数
+    /// - `expr`: 対象の式。
+    /// - `operator`: 更新を行う演算子のトークン。エラーの位置に使用します。
+    ///
+    /// # 戻り値
+    /// - 成功時: 変数、プロパティ、添字のいずれかであればその式。
+    /// - 失敗時: それ以外の式の場合の `LoxError`。
+    fn update_target(expr: Expr, operator: &Token) -> Result<Expr, LoxError> {
+        match expr {
+            Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. } => Ok(expr),
+            _ => Err(Self::error(operator, "Invalid assignment target.")),
+        }
+    }
+
+    /// 指定したトークンの位置を持つパースエラーを生成します。
+    ///
+    /// # 引数
+    /// - `token`: エラーの原因となったトークン。
+    /// - `message`: エラーメッセージ。
+    ///
+    /// # 戻り値
+    /// 発生箇所を持つ `LoxError::ParseError`。
+    fn error(token: &Token, message: &str) -> LoxError {
+        LoxError::ParseError(message.to_string()).at(token.span())
+    }
+
+    /// 発生箇所が特定されていないエラーに、現在のトークンの位置を付与します。
+    ///
+    /// # 引数
+    /// - `err`: 対象のエラー。
+    ///
+    /// # 戻り値
+    /// 発生箇所を持つ `LoxError`。
+    fn locate(&self, err: LoxError) -> LoxError {
+        match self.peek().or(self.tokens.last()) {
+            Some(token) => err.at(token.span()),
+            None => err,
+        }
+    }
+
     /// 指定されたトークンタイプが現在の位置に一致する場合にトークンを消費します。
     ///
     /// # 引数
@@ -410,7 +817,7 @@ impl Parser {
         if self.check(token_type) {
             Ok(self.advance())
         } else {
-            Err(LoxError::ParseError(message.to_string()))
+            Err(self.locate(LoxError::ParseError(message.to_string())))
         }
     }
 
@@ -445,9 +852,9 @@ impl Parser {
     /// - 失敗時: `LoxError`。
     fn expression(&mut self) -> Result<Expr, LoxError> {
         if self.recursion_depth > MAX_RECURSION_DEPTH {
-            return Err(LoxError::ParseError(
+            return Err(self.locate(LoxError::ParseError(
                 "Recursion depth exceeded.".to_string(),
-            ));
+            )));
         }
         self.recursion_depth += 1;
         let result = self.assignment();
@@ -455,6 +862,58 @@ impl Parser {
         result
     }
 
+    /// 論理和の式を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a or b`
+    ///
+    /// # 処理の流れ
+    /// 1. 論理積の式を解析します。
+    /// 2. `or` が続く限りループします。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Logical` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn or(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.and()?;
+
+        while self.match_token(&[TokenType::Or]) {
+            let operator = self.previous().clone();
+            let right = self.and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// 論理積の式を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a and b`
+    ///
+    /// # 処理の流れ
+    /// 1. 等価性の式を解析します。
+    /// 2. `and` が続く限りループします。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Logical` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn and(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.equality()?;
+
+        while self.match_token(&[TokenType::And]) {
+            let operator = self.previous().clone();
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
     /// 等価性の式を解析し、対応する `Expr` を生成します。
     ///
     /// 例: `a == b` または `a != b`
@@ -486,14 +945,14 @@ impl Parser {
     /// 例: `a > b`, `a >= b`, `a < b`, `a <= b`
     ///
     /// # 処理の流れ
-    /// 1. 項を解析します。
+    /// 1. ビット論理和を解析します。
     /// 2. 比較演算子が続く限りループします。
     ///
     /// # 戻り値
     /// - 成功時: `Expr::Binary` またはその代わりの式。
     /// - 失敗時: `LoxError`。
     fn comparison(&mut self) -> Result<Expr, LoxError> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while self.match_token(&[
             TokenType::Greater,
@@ -502,14 +961,100 @@ impl Parser {
             TokenType::LessEqual,
         ]) {
             let operator = self.previous().clone();
-            let right = self.term()?;
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// ビット論理和を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a | b`
+    ///
+    /// ビット演算子は比較演算子より強く結合するため、`a & 1 == 0` は `(a & 1) == 0` と解釈されます。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Binary` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn bit_or(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.bit_xor()?;
+        while self.match_token(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// ビット排他的論理和を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a ^ b`
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Binary` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn bit_xor(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.bit_and()?;
+        while self.match_token(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// ビット論理積を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a & b`
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Binary` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn bit_and(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.shift()?;
+        while self.match_token(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right),
             };
         }
+        Ok(expr)
+    }
 
+    /// シフト演算を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `a << 2` または `a >> 1`
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Binary` またはその代わりの式。
+    /// - 失敗時: `LoxError`。
+    fn shift(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.term()?;
+        while self.match_token(&[TokenType::LessLess, TokenType::GreaterGreater]) {
+            let operator = self.previous().clone();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
         Ok(expr)
     }
 
@@ -540,18 +1085,23 @@ impl Parser {
 
     /// 因子（乗除算）を解析し、対応する `Expr` を生成します。
     ///
-    /// 例: `a * b` または `a / b`
+    /// 例: `a * b`、`a / b`、`a % b`、`a ~/ b`
     ///
     /// # 処理の流れ
     /// 1. 単項式を解析します。
-    /// 2. `*`, `/`, `%` が続く限りループします。
+    /// 2. `*`, `/`, `%`, `~/` が続く限りループします。
     ///
     /// # 戻り値
     /// - 成功時: `Expr::Binary` またはその代わりの式。
     /// - 失敗時: `LoxError`。
     fn factor(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.unary()?;
-        while self.match_token(&[TokenType::Slash, TokenType::Star, TokenType::Percent]) {
+        while self.match_token(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
             expr = Expr::Binary {
@@ -565,17 +1115,27 @@ impl Parser {
 
     /// 単項式を解析し、対応する `Expr` を生成します。
     ///
-    /// 例: `!a` または `-a`
+    /// 例: `!a`、`-a`、`~a`、`++a`
     ///
     /// # 処理の流れ
-    /// 1. `!` または `-` があれば再帰的に解析します。
-    /// 2. それ以外の場合は基本式（`primary`）を解析します。
+    /// 1. `!`、`-`、`~` があれば再帰的に解析します。
+    /// 2. 前置の `++`、`--` があれば対象を解析して `Expr::Increment` を生成します。
+    /// 3. それ以外の場合はべき乗（`power`）を解析します。
     ///
     /// # 戻り値
-    /// - 成功時: `Expr::Unary` または基本式。
+    /// - 成功時: `Expr::Unary`、`Expr::Increment` またはべき乗の式。
     /// - 失敗時: `LoxError`。
     fn unary(&mut self) -> Result<Expr, LoxError> {
-        if self.match_token(&[TokenType::Bang, TokenType::Minus]) {
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.unary()?;
+            return Ok(Expr::Increment {
+                target: Box::new(Self::update_target(target, &operator)?),
+                operator,
+                prefix: true,
+            });
+        }
+        if self.match_token(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
             return Ok(Expr::Unary {
@@ -583,7 +1143,136 @@ impl Parser {
                 operand: Box::new(right),
             });
         }
-        self.primary()
+        self.power()
+    }
+
+    /// べき乗を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `2 ** 3 ** 2` は `2 ** (3 ** 2)` と解釈されます（右結合）。
+    ///
+    /// `**` は左側の単項演算子より強く結合するため、`-2 ** 2` は `-(2 ** 2)` になります。
+    /// 右辺には単項式を書くことができます（例: `2 ** -1`）。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Binary` または後置式。
+    /// - 失敗時: `LoxError`。
+    fn power(&mut self) -> Result<Expr, LoxError> {
+        let expr = self.postfix()?;
+        if self.match_token(&[TokenType::StarStar]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
+    /// 後置の `++`、`--` を解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `i++`、`point.x--`
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Increment` または呼び出し式。
+    /// - 失敗時: `LoxError`。
+    fn postfix(&mut self) -> Result<Expr, LoxError> {
+        let expr = self.call()?;
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            return Ok(Expr::Increment {
+                target: Box::new(Self::update_target(expr, &operator)?),
+                operator,
+                prefix: false,
+            });
+        }
+        Ok(expr)
+    }
+
+    /// 関数呼び出し、プロパティアクセス、添字アクセスを解析し、対応する `Expr` を生成します。
+    ///
+    /// 例: `f(1, 2)`, `point.x`, `obj.method()(arg)`, `xs[0]`
+    ///
+    /// # 処理の流れ
+    /// 1. 基本式を解析します。
+    /// 2. `(` が続く場合は引数リストを解析して `Expr::Call` を生成します。
+    /// 3. `.` が続く場合はプロパティ名を取得して `Expr::Get` を生成します。
+    /// 4. `[` が続く場合は添字の式を解析して `Expr::Index` を生成します。
+    /// 5. いずれも続かなくなるまで繰り返します。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Call`、`Expr::Get`、`Expr::Index` または基本式。
+    /// - 失敗時: `LoxError`。
+    fn call(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name = self
+                    .consume(TokenType::Identifier, "Expect property name after '.'.")?
+                    .clone();
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_token(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// 関数呼び出しの引数リストを解析します。
+    ///
+    /// `(` は呼び出し元で既に消費されている必要があります。
+    ///
+    /// # 引数
+    /// - `callee`: 呼び出される式。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Expr::Call` 型の式。
+    /// - 失敗時: `LoxError`。
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxError> {
+        let mut arguments = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                // `...` が前置された引数はリストを展開する
+                if self.match_token(&[TokenType::Ellipsis]) {
+                    let ellipsis = self.previous().clone();
+                    arguments.push(Expr::Spread {
+                        ellipsis,
+                        expression: Box::new(self.expression()?),
+                    });
+                } else {
+                    arguments.push(self.expression()?);
+                }
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self
+            .consume(TokenType::RightParen, "Expect ')' after arguments.")?
+            .clone();
+
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
     }
 
     /// 基本式を解析し、対応する `Expr` を生成します。
@@ -593,8 +1282,14 @@ impl Parser {
     /// # 処理の流れ
     /// - 数値リテラル: `1`, `3.14`
     /// - 文字列リテラル: `"hello"`
+    /// - 文字列補間: `"Hello ${name}"`
     /// - 識別子（変数）
+    /// - `this`
+    /// - `super.method`
     /// - グループ化: `(expr)`
+    /// - リストリテラル: `[1, 2, 3]`
+    /// - 無名関数: `fun (a, b) { return a + b; }`
+    /// - マップリテラル: `{"key": value}`（式の位置に現れる `{` はブロックではなくマップとして扱います）
     ///
     /// # 戻り値
     /// - 成功時: `Expr` 型の基本式。
@@ -604,41 +1299,44 @@ impl Parser {
             if let Some(literal) = &self.previous().literal {
                 return Ok(Expr::Literal {
                     value: literal.clone(),
+                    token: self.previous().clone(),
                 });
             }
         }
 
         if self.match_token(&[TokenType::StringLit]) {
-            if let Some(literal) = &self.previous().literal {
-                if let LiteralValue::String(s) = literal {
-                    return Ok(Expr::Literal {
-                        value: LiteralValue::String(s.clone()),
-                    });
-                }
+            if let Some(LiteralValue::String(s)) = &self.previous().literal {
+                return Ok(Expr::Literal {
+                    value: LiteralValue::String(s.clone()),
+                    token: self.previous().clone(),
+                });
             }
         }
 
+        if self.match_token(&[TokenType::StringInterpolation]) {
+            return self.interpolation();
+        }
+
         if self.match_token(&[TokenType::Identifier]) {
             let variable = self.previous().clone();
+            return Ok(Expr::Variable {
+                name: variable,
+                depth: None,
+            });
+        }
 
-            if self.match_token(&[TokenType::LeftParen]) {
-                let mut arguments = Vec::new();
-                if !self.check(TokenType::RightParen) {
-                    loop {
-                        arguments.push(self.expression()?);
-                        if !self.match_token(&[TokenType::Comma]) {
-                            break;
-                        }
-                    }
-                }
-                self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
-                return Ok(Expr::Call {
-                    callee: Box::new(Expr::Variable { name: variable }),
-                    arguments,
-                });
-            }
+        if self.match_token(&[TokenType::This]) {
+            let keyword = self.previous().clone();
+            return Ok(Expr::This { keyword });
+        }
 
-            return Ok(Expr::Variable { name: variable });
+        if self.match_token(&[TokenType::Super]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self
+                .consume(TokenType::Identifier, "Expect superclass method name.")?
+                .clone();
+            return Ok(Expr::Super { keyword, method });
         }
 
         if self.match_token(&[TokenType::LeftParen]) {
@@ -649,19 +1347,72 @@ impl Parser {
             });
         }
 
+        if self.match_token(&[TokenType::Fun]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+            let (params, body) = self.function_body("function")?;
+            return Ok(Expr::Lambda {
+                keyword,
+                params,
+                body,
+            });
+        }
+
+        if self.match_token(&[TokenType::LeftBracket]) {
+            let bracket = self.previous().clone();
+            let mut elements = Vec::new();
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List { bracket, elements });
+        }
+
+        if self.match_token(&[TokenType::LeftBrace]) {
+            let brace = self.previous().clone();
+            let mut entries = Vec::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map { brace, entries });
+        }
+
         if self.match_token(&[TokenType::True]) {
             return Ok(Expr::Literal {
                 value: LiteralValue::Boolean(true),
+                token: self.previous().clone(),
             });
         }
 
         if self.match_token(&[TokenType::False]) {
             return Ok(Expr::Literal {
                 value: LiteralValue::Boolean(false),
+                token: self.previous().clone(),
+            });
+        }
+
+        if self.match_token(&[TokenType::Nil]) {
+            return Ok(Expr::Literal {
+                value: LiteralValue::Nil,
+                token: self.previous().clone(),
             });
         }
 
-        Err(LoxError::ParseError("Unexpected token.".to_string()))
+        Err(self.locate(LoxError::ParseError("Unexpected token.".to_string())))
     }
 
     /// 指定されたトークンタイプが現在の位置にある場合にトークンを消費します。
@@ -698,6 +1449,19 @@ impl Parser {
         }
     }
 
+    /// 次のトークンの1つ先のトークンが指定された種類かを確認します。
+    ///
+    /// # 引数
+    /// - `token_type`: 確認するトークンの種類。
+    ///
+    /// # 戻り値
+    /// - 一致する場合は `true`、それ以外は `false`。
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
     /// パーサーがすべてのトークンを解析し終えたかを確認します。
     ///
     /// # 戻り値
@@ -748,8 +1512,6 @@ impl Parser {
     /// - 成功時: `Stmt::Function` 型の関数定義ステートメント。
     /// - 失敗時: `LoxError`。
     fn function(&mut self, kind: &str) -> Result<Stmt, LoxError> {
-        self.enter_function();
-
         // 関数名を取得
         let name = self
             .consume(TokenType::Identifier, &format!("Expect {} name.", kind))?
@@ -761,44 +1523,94 @@ impl Parser {
             &format!("Expect '(' after {} name.", kind),
         )?;
 
-        let mut params: Vec<(Token, Option<Expr>)> = Vec::new();
+        let (params, body) = self.function_body(kind)?;
+
+        // ステートメントを生成
+        Ok(Stmt::Function { name, params, body })
+    }
+
+    /// 関数のパラメータリストと本体を解析します。
+    ///
+    /// 名前付きの関数定義と無名関数の両方で使用します。呼び出し時点で `(` は消費済みです。
+    ///
+    /// # 引数
+    /// - `kind`: 関数の種類を示す文字列（例: "function"）。
+    ///
+    /// # 戻り値
+    /// - 成功時: パラメータのリストと本体のステートメントの組。
+    /// - 失敗時: `LoxError`。
+    fn function_body(&mut self, kind: &str) -> Result<(Vec<Parameter>, Vec<Stmt>), LoxError> {
+        let mut params: Vec<Parameter> = Vec::new();
 
         // パラメータの解析
         if !self.check(TokenType::RightParen) {
             loop {
+                // `...` が前置されたパラメータは可変長パラメータ
+                let rest = self.match_token(&[TokenType::Ellipsis]);
+
                 // パラメータ名を取得
                 let param_name = self
                     .consume(TokenType::Identifier, "Expect parameter name.")?
                     .clone();
 
                 // パラメータ名の重複チェック
-                for (existing_param, _) in &params {
-                    if existing_param.lexeme == param_name.lexeme {
-                        return Err(LoxError::ParseError(format!(
-                            "Duplicate parameter name '{}'.",
-                            param_name.lexeme
-                        )));
+                for existing_param in &params {
+                    if existing_param.name.lexeme == param_name.lexeme {
+                        return Err(Self::error(
+                            &param_name,
+                            &format!("Duplicate parameter name '{}'.", param_name.lexeme),
+                        ));
                     }
                 }
 
                 // デフォルト値が指定されている場合
                 let default_value = if self.match_token(&[TokenType::Equal]) {
-                    Some(self.expression().map_err(|e| {
-                        LoxError::ParseError(format!(
-                            "Invalid default value for parameter '{}': {}",
-                            param_name.lexeme, e
-                        ))
-                    })?)
+                    Some(self.expression()?)
                 } else {
                     None
                 };
 
-                params.push((param_name, default_value));
+                if rest && default_value.is_some() {
+                    return Err(Self::error(
+                        &param_name,
+                        &format!(
+                            "Rest parameter '{}' can't have a default value.",
+                            param_name.lexeme
+                        ),
+                    ));
+                }
+
+                // デフォルト値を持つパラメータの後に必須パラメータは置けない
+                if !rest
+                    && default_value.is_none()
+                    && params.iter().any(|param| param.default.is_some())
+                {
+                    return Err(Self::error(
+                        &param_name,
+                        &format!(
+                            "Parameter '{}' without a default value can't follow parameters with defaults.",
+                            param_name.lexeme
+                        ),
+                    ));
+                }
+
+                params.push(Parameter {
+                    name: param_name,
+                    default: default_value,
+                    rest,
+                });
 
                 // カンマがない場合は終了
                 if !self.match_token(&[TokenType::Comma]) {
                     break;
                 }
+
+                // 可変長パラメータの後にパラメータは置けない
+                if rest {
+                    return Err(self.locate(LoxError::ParseError(
+                        "Rest parameter must be the last parameter.".to_string(),
+                    )));
+                }
             }
         }
 
@@ -811,8 +1623,11 @@ impl Parser {
             &format!("Expect '{{' before {} body.", kind),
         )?;
 
-        // 関数本体のブロックを解析
-        let body = match self.block()? {
+        // 関数本体のブロックを解析（関数の外側のループに対する `break` / `continue` は許可しない）
+        let enclosing_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.block();
+        self.loop_depth = enclosing_loop_depth;
+        let body = match body? {
             Stmt::Block(statements) => statements,
             _ => {
                 return Err(LoxError::ParseError(
@@ -821,28 +1636,6 @@ impl Parser {
             }
         };
 
-        // 関数の解析終了後に exit_function を呼び出す
-        self.exit_function();
-
-        // ステートメントを生成
-        Ok(Stmt::Function {
-            name,
-            params: params.into_iter().map(|(token, _)| token).collect(),
-            body,
-        })
-    }
-
-    /// 関数の開始時に `in_function` を `true` に設定し、関数の終了時に `false` に設定するメソッドです。
-    /// これにより、`return_statement` が関数内でのみ動作するようにします。
-    ///
-    /// # 処理の流れ
-    /// 1. 関数の開始時に呼び出され、`in_function` を `true` に設定します。
-    /// 2. 関数の終了時に呼び出され、`in_function` を `false` に戻します。
-    fn enter_function(&mut self) {
-        self.in_function = true;
-    }
-
-    fn exit_function(&mut self) {
-        self.in_function = false;
+        Ok((params, body))
     }
 }
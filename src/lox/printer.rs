@@ -1,5 +1,5 @@
-use crate::lox::ast::Expr;
 use crate::lox::ast::Stmt;
+use crate::lox::ast::{Expr, Parameter};
 use crate::lox::token::Token;
 use crate::lox::token_type::LiteralValue;
 
@@ -13,6 +13,9 @@ pub trait Visitor<R> {
     /// バイナリ式（例: 加算や減算）を訪問します。
     fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> R;
 
+    /// 論理式（`and` / `or`）を訪問します。
+    fn visit_logical(&mut self, left: &Expr, operator: &Token, right: &Expr) -> R;
+
     /// リテラル値（例: 数値や文字列）を訪問します。
     fn visit_literal(&mut self, value: &LiteralValue) -> R;
 
@@ -25,9 +28,18 @@ pub trait Visitor<R> {
     /// 単項演算子（例: `-` や `!`）を訪問します。
     fn visit_unary(&mut self, operator: &Token, operand: &Expr) -> R;
 
+    /// 条件式を訪問します。
+    fn visit_conditional(&mut self, condition: &Expr, then_branch: &Expr, else_branch: &Expr) -> R;
+
     /// 変数の代入を訪問します。
     fn visit_assign(&mut self, name: &Token, value: &Expr) -> R;
 
+    /// 複合代入を訪問します。
+    fn visit_compound_assign(&mut self, target: &Expr, operator: &Token, value: &Expr) -> R;
+
+    /// インクリメント・デクリメントを訪問します。
+    fn visit_increment(&mut self, target: &Expr, operator: &Token, prefix: bool) -> R;
+
     /// 関数呼び出しを訪問します。
     fn visit_call(&mut self, callee: &Expr, arguments: &[Expr]) -> R;
 
@@ -37,6 +49,27 @@ pub trait Visitor<R> {
     /// オブジェクトのプロパティ設定を訪問します。
     fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> R;
 
+    /// リストリテラルを訪問します。
+    fn visit_list(&mut self, elements: &[Expr]) -> R;
+
+    /// スプレッド引数を訪問します。
+    fn visit_spread(&mut self, expression: &Expr) -> R;
+
+    /// 無名関数を訪問します。
+    fn visit_lambda(&mut self, params: &[Parameter], body: &[Stmt]) -> R;
+
+    /// 文字列補間を訪問します。
+    fn visit_interpolation(&mut self, parts: &[Expr]) -> R;
+
+    /// マップリテラルを訪問します。
+    fn visit_map(&mut self, entries: &[(Expr, Expr)]) -> R;
+
+    /// 添字による要素の取得を訪問します。
+    fn visit_index(&mut self, object: &Expr, index: &Expr) -> R;
+
+    /// 添字による要素の設定を訪問します。
+    fn visit_set_index(&mut self, object: &Expr, index: &Expr, value: &Expr) -> R;
+
     /// `this` キーワードを訪問します。
     fn visit_this(&mut self, keyword: &Token) -> R;
 
@@ -73,13 +106,35 @@ pub trait Visitor<R> {
     fn visit_if(&mut self, condition: &Expr, then_branch: &Stmt, else_branch: &Option<Stmt>) -> R;
 
     /// 関数宣言を訪問します。
-    fn visit_function(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> R;
+    fn visit_function(&mut self, name: &Token, params: &[Parameter], body: &[Stmt]) -> R;
 
     /// `return` 文を訪問します。
     fn visit_return(&mut self, keyword: &Token, value: &Option<Expr>) -> R;
 
+    /// `break` 文を訪問します。
+    fn visit_break(&mut self, keyword: &Token) -> R;
+
+    /// `continue` 文を訪問します。
+    fn visit_continue(&mut self, keyword: &Token) -> R;
+
+    /// `throw` 文を訪問します。
+    fn visit_throw(&mut self, keyword: &Token, value: &Expr) -> R;
+
+    /// `try` 文を訪問します。
+    fn visit_try(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<(Token, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> R;
+
     /// クラス宣言を訪問します。
-    fn visit_class(&mut self, name: &Token, methods: &[(Token, Stmt)]) -> R;
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[(Token, Stmt)],
+    ) -> R;
 }
 
 /// 抽象構文木（AST）のノードを文字列形式で表現するプリンタ。
@@ -96,6 +151,27 @@ impl AstPrinter {
     pub fn print(&mut self, expr: &Expr) -> String {
         expr.accept(self)
     }
+
+    /// パラメータリストを文字列として返します。
+    ///
+    /// デフォルト値は `name = value`、可変長パラメータは `...name` の形式で表します。
+    ///
+    /// # 引数
+    /// - `params`: パラメータのリスト。
+    ///
+    /// # 戻り値
+    /// パラメータをカンマ区切りで連結した文字列。
+    fn parameters(&mut self, params: &[Parameter]) -> String {
+        params
+            .iter()
+            .map(|param| match &param.default {
+                Some(default) => format!("{} = {}", param.name.lexeme, default.accept(self)),
+                None if param.rest => format!("...{}", param.name.lexeme),
+                None => param.name.lexeme.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
 impl Visitor<String> for AstPrinter {
@@ -117,6 +193,24 @@ impl Visitor<String> for AstPrinter {
         )
     }
 
+    /// 論理式（`and` / `or`）。
+    ///
+    /// # 引数
+    /// - `left`: 左辺の式。
+    /// - `operator`: 論理演算子。
+    /// - `right`: 右辺の式。
+    ///
+    /// # 戻り値
+    /// 論理式を文字列で表現した結果。
+    fn visit_logical(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        format!(
+            "({} {} {})",
+            operator.lexeme,
+            left.accept(self),
+            right.accept(self)
+        )
+    }
+
     /// リテラル値（例: 数値や文字列）。
     ///
     /// # 引数
@@ -174,6 +268,64 @@ impl Visitor<String> for AstPrinter {
         format!("(assign {} {})", name.lexeme, value.accept(self))
     }
 
+    /// 条件式。
+    ///
+    /// # 引数
+    /// - `condition`: 条件の式。
+    /// - `then_branch`: 条件が真の場合の式。
+    /// - `else_branch`: 条件が偽の場合の式。
+    ///
+    /// # 戻り値
+    /// 条件式を文字列で表現した結果。
+    fn visit_conditional(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        format!(
+            "(? {} {} {})",
+            condition.accept(self),
+            then_branch.accept(self),
+            else_branch.accept(self)
+        )
+    }
+
+    /// 複合代入。
+    ///
+    /// # 引数
+    /// - `target`: 代入対象の式。
+    /// - `operator`: 複合代入演算子（例: `+=`）。
+    /// - `value`: 右辺の式。
+    ///
+    /// # 戻り値
+    /// 複合代入を文字列で表現した結果。
+    fn visit_compound_assign(&mut self, target: &Expr, operator: &Token, value: &Expr) -> String {
+        format!(
+            "({} {} {})",
+            operator.lexeme,
+            target.accept(self),
+            value.accept(self)
+        )
+    }
+
+    /// インクリメント・デクリメント。
+    ///
+    /// # 引数
+    /// - `target`: 対象の式。
+    /// - `operator`: `++` または `--`。
+    /// - `prefix`: 前置の場合は `true`。
+    ///
+    /// # 戻り値
+    /// 前置は `(++ x)`、後置は `(x ++)` の形式で表現した結果。
+    fn visit_increment(&mut self, target: &Expr, operator: &Token, prefix: bool) -> String {
+        if prefix {
+            format!("({} {})", operator.lexeme, target.accept(self))
+        } else {
+            format!("({} {})", target.accept(self), operator.lexeme)
+        }
+    }
+
     /// 関数呼び出し。
     ///
     /// # 引数
@@ -221,6 +373,113 @@ impl Visitor<String> for AstPrinter {
         )
     }
 
+    /// リストリテラル。
+    ///
+    /// # 引数
+    /// - `elements`: リストの要素となる式。
+    ///
+    /// # 戻り値
+    /// リストリテラルを文字列で表現した結果。
+    fn visit_list(&mut self, elements: &[Expr]) -> String {
+        let elements_str = elements
+            .iter()
+            .map(|element| element.accept(self))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("(list {})", elements_str)
+    }
+
+    /// スプレッド引数。
+    ///
+    /// # 引数
+    /// - `expression`: 展開されるリストの式。
+    ///
+    /// # 戻り値
+    /// スプレッド引数を文字列で表現した結果。
+    fn visit_spread(&mut self, expression: &Expr) -> String {
+        format!("(...{})", expression.accept(self))
+    }
+
+    /// 無名関数。
+    ///
+    /// # 引数
+    /// - `params`: パラメータのリスト。
+    /// - `body`: 関数の本体。
+    ///
+    /// # 戻り値
+    /// 無名関数を文字列で表現した結果。
+    fn visit_lambda(&mut self, params: &[Parameter], body: &[Stmt]) -> String {
+        let params_str = self.parameters(params);
+        let body_str = body
+            .iter()
+            .map(|stmt| stmt.accept(self))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("(fun ({}) {})", params_str, body_str)
+    }
+
+    /// 文字列補間。
+    ///
+    /// # 引数
+    /// - `parts`: 連結される文字列部分と埋め込み式。
+    ///
+    /// # 戻り値
+    /// 文字列補間を文字列で表現した結果。
+    fn visit_interpolation(&mut self, parts: &[Expr]) -> String {
+        let parts_str = parts
+            .iter()
+            .map(|part| part.accept(self))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("(interpolate {})", parts_str)
+    }
+
+    /// マップリテラル。
+    ///
+    /// # 引数
+    /// - `entries`: キーと値の式の組。
+    ///
+    /// # 戻り値
+    /// マップリテラルを文字列で表現した結果。
+    fn visit_map(&mut self, entries: &[(Expr, Expr)]) -> String {
+        let entries_str = entries
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.accept(self), value.accept(self)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("(map {})", entries_str)
+    }
+
+    /// 添字による要素の取得。
+    ///
+    /// # 引数
+    /// - `object`: 対象の式。
+    /// - `index`: 添字の式。
+    ///
+    /// # 戻り値
+    /// 要素の取得を文字列で表現した結果。
+    fn visit_index(&mut self, object: &Expr, index: &Expr) -> String {
+        format!("(index {}[{}])", object.accept(self), index.accept(self))
+    }
+
+    /// 添字による要素の設定。
+    ///
+    /// # 引数
+    /// - `object`: 対象の式。
+    /// - `index`: 添字の式。
+    /// - `value`: 設定する値。
+    ///
+    /// # 戻り値
+    /// 要素の設定を文字列で表現した結果。
+    fn visit_set_index(&mut self, object: &Expr, index: &Expr, value: &Expr) -> String {
+        format!(
+            "(set-index {}[{}] = {})",
+            object.accept(self),
+            index.accept(self),
+            value.accept(self)
+        )
+    }
+
     /// `this` キーワード。
     ///
     /// # 引数
@@ -379,12 +638,8 @@ impl Visitor<String> for AstPrinter {
     ///
     /// # 戻り値
     /// 関数宣言を文字列で表現した結果。
-    fn visit_function(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> String {
-        let params_str = params
-            .iter()
-            .map(|param| param.lexeme.clone())
-            .collect::<Vec<_>>()
-            .join(", ");
+    fn visit_function(&mut self, name: &Token, params: &[Parameter], body: &[Stmt]) -> String {
+        let params_str = self.parameters(params);
         let body_str = body
             .iter()
             .map(|stmt| stmt.accept(self))
@@ -401,7 +656,7 @@ impl Visitor<String> for AstPrinter {
     ///
     /// # 戻り値
     /// `return` 文を文字列で表現した結果。
-    fn visit_return(&mut self, keyword: &Token, value: &Option<Expr>) -> String {
+    fn visit_return(&mut self, _keyword: &Token, value: &Option<Expr>) -> String {
         if let Some(val) = value {
             format!("(return {})", self.print(val))
         } else {
@@ -409,15 +664,89 @@ impl Visitor<String> for AstPrinter {
         }
     }
 
+    /// `break` 文。
+    ///
+    /// # 引数
+    /// - `keyword`: `break` キーワード。
+    ///
+    /// # 戻り値
+    /// `break` 文を文字列で表現した結果。
+    fn visit_break(&mut self, keyword: &Token) -> String {
+        format!("({})", keyword.lexeme)
+    }
+
+    /// `continue` 文。
+    ///
+    /// # 引数
+    /// - `keyword`: `continue` キーワード。
+    ///
+    /// # 戻り値
+    /// `continue` 文を文字列で表現した結果。
+    fn visit_continue(&mut self, keyword: &Token) -> String {
+        format!("({})", keyword.lexeme)
+    }
+
+    /// `throw` 文。
+    ///
+    /// # 引数
+    /// - `keyword`: `throw` キーワード。
+    /// - `value`: 送出する値の式。
+    ///
+    /// # 戻り値
+    /// `throw` 文を文字列で表現した結果。
+    fn visit_throw(&mut self, keyword: &Token, value: &Expr) -> String {
+        format!("({} {})", keyword.lexeme, self.print(value))
+    }
+
+    /// `try` 文。
+    ///
+    /// # 引数
+    /// - `body`: `try` ブロックのステートメント。
+    /// - `catch`: 例外を受け取る変数名と `catch` ブロック（オプション）。
+    /// - `finally`: `finally` ブロック（オプション）。
+    ///
+    /// # 戻り値
+    /// `try` 文を文字列で表現した結果。
+    fn visit_try(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<(Token, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> String {
+        let mut result = format!("(try {}", self.visit_block(body));
+        if let Some((name, statements)) = catch {
+            result.push_str(&format!(
+                " (catch {} {})",
+                name.lexeme,
+                self.visit_block(statements)
+            ));
+        }
+        if let Some(statements) = finally {
+            result.push_str(&format!(" (finally {})", self.visit_block(statements)));
+        }
+        result.push(')');
+        result
+    }
+
     /// クラス宣言。
     ///
     /// # 引数
     /// - `name`: クラス名。
+    /// - `superclass`: スーパークラスを参照する式（オプション）。
     /// - `methods`: クラスのメソッドリスト。
     ///
     /// # 戻り値
     /// クラス宣言を文字列で表現した結果。
-    fn visit_class(&mut self, name: &Token, methods: &[(Token, Stmt)]) -> String {
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[(Token, Stmt)],
+    ) -> String {
+        let superclass_str = superclass
+            .as_ref()
+            .map(|expr| format!(" < {}", self.print(expr)))
+            .unwrap_or_default();
         let methods_str = methods
             .iter()
             .map(|(method_name, method_stmt)| {
@@ -425,6 +754,9 @@ impl Visitor<String> for AstPrinter {
             })
             .collect::<Vec<_>>()
             .join(" ");
-        format!("(class {} {{ {} }})", name.lexeme, methods_str)
+        format!(
+            "(class {}{} {{ {} }})",
+            name.lexeme, superclass_str, methods_str
+        )
     }
 }
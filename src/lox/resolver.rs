@@ -0,0 +1,460 @@
+use crate::lox::ast::{Expr, Parameter, Stmt};
+use crate::lox::error::LoxError;
+use crate::lox::token::Token;
+use std::collections::HashMap;
+
+/// 解析中の関数の種類。
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionType {
+    /// 関数の外側（トップレベル）
+    None,
+    /// 通常の関数
+    Function,
+    /// クラスのメソッド
+    Method,
+    /// クラスの初期化メソッド `init`
+    Initializer,
+}
+
+/// 解析中のクラスの種類。
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassType {
+    /// クラスの外側
+    None,
+    /// スーパークラスを持たないクラス
+    Class,
+    /// スーパークラスを持つクラス
+    Subclass,
+}
+
+/// 変数の参照先スコープを静的に解決するリゾルバ。
+///
+/// パーサーが生成したステートメントを評価前に走査し、各 `Expr::Variable` と `Expr::Assign` に
+/// 参照先スコープまでの距離を書き込みます。あわせて、トップレベルでの `return` や
+/// 同一スコープでの重複宣言などの静的エラーを検出します。
+///
+/// スコープの構造は `Evaluator` が実行時に作成する環境と一致させる必要があります。
+///
+/// # フィールド
+/// - `scopes`: ローカルスコープのスタック。値は変数の初期化が完了しているかどうかを表します。
+/// - `current_function`: 解析中の関数の種類。
+/// - `current_class`: 解析中のクラスの種類。
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    /// 新しい `Resolver` インスタンスを作成します。
+    ///
+    /// # 戻り値
+    /// - 新しい `Resolver` インスタンス。
+    pub fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+        }
+    }
+
+    /// ステートメントのリストを解析し、変数参照のスコープ距離を書き込みます。
+    ///
+    /// # 引数
+    /// - `statements`: 解析対象のステートメントのリスト。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: 最初に検出された静的エラー `LoxError`。
+    pub fn resolve(&mut self, statements: &mut [Stmt]) -> Result<(), LoxError> {
+        for stmt in statements {
+            self.resolve_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    /// ステートメントを解析します。
+    ///
+    /// # 引数
+    /// - `stmt`: 解析対象のステートメント。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: `LoxError`。
+    fn resolve_stmt(&mut self, stmt: &mut Stmt) -> Result<(), LoxError> {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.resolve_expr(expr),
+            Stmt::Var { name, initializer } => {
+                self.declare(name)?;
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer)?;
+                }
+                self.define(name);
+                Ok(())
+            }
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                self.resolve(statements)?;
+                self.end_scope();
+                Ok(())
+            }
+            Stmt::While(condition, body) => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(body)
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                // 初期化式の変数はループ専用のスコープに置かれる
+                self.begin_scope();
+                if let Some(initializer) = initializer {
+                    self.resolve_stmt(initializer)?;
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expr(condition)?;
+                }
+                self.resolve_stmt(body)?;
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment)?;
+                }
+                self.end_scope();
+                Ok(())
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch)?;
+                }
+                Ok(())
+            }
+            Stmt::Function { name, params, body } => {
+                // 再帰呼び出しを許可するため、本体の解析前に名前を定義する
+                self.declare(name)?;
+                self.define(name);
+                self.resolve_function(params, body, FunctionType::Function)
+            }
+            Stmt::Return { keyword, value } => {
+                if self.current_function == FunctionType::None {
+                    return Err(LoxError::ReturnOutsideFunction.at(keyword.span()));
+                }
+                if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        return Err(LoxError::ResolveError(
+                            "Can't return a value from an initializer.".to_string(),
+                        )
+                        .at(keyword.span()));
+                    }
+                    self.resolve_expr(value)?;
+                }
+                Ok(())
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+
+                self.declare(name)?;
+                self.define(name);
+
+                // スーパークラスを持つ場合、`super` を保持するスコープでメソッドを囲む
+                if let Some(superclass) = superclass {
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expr(superclass)?;
+                    self.begin_scope();
+                    self.define_name("super");
+                }
+
+                for (_, method) in methods.iter_mut() {
+                    if let Stmt::Function { name, params, body } = method {
+                        let kind = if name.lexeme == "init" {
+                            FunctionType::Initializer
+                        } else {
+                            FunctionType::Method
+                        };
+                        self.resolve_function(params, body, kind)?;
+                    }
+                }
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+
+                self.current_class = enclosing_class;
+                Ok(())
+            }
+            Stmt::Assign { value, .. } => self.resolve_expr(value),
+            Stmt::Break { .. } | Stmt::Continue { .. } => Ok(()),
+            Stmt::Throw { value, .. } => self.resolve_expr(value),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                self.begin_scope();
+                self.resolve(body)?;
+                self.end_scope();
+
+                // 例外を受け取る変数は `catch` ブロックのスコープに置かれる
+                if let Some((name, statements)) = catch {
+                    self.begin_scope();
+                    self.declare(name)?;
+                    self.define(name);
+                    self.resolve(statements)?;
+                    self.end_scope();
+                }
+
+                if let Some(statements) = finally {
+                    self.begin_scope();
+                    self.resolve(statements)?;
+                    self.end_scope();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 式を解析します。
+    ///
+    /// # 引数
+    /// - `expr`: 解析対象の式。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: `LoxError`。
+    fn resolve_expr(&mut self, expr: &mut Expr) -> Result<(), LoxError> {
+        match expr {
+            Expr::Variable { name, depth } => {
+                if let Some(scope) = self.scopes.last() {
+                    if scope.get(&name.lexeme) == Some(&false) {
+                        return Err(LoxError::ResolveError(format!(
+                            "Can't read local variable '{}' in its own initializer.",
+                            name.lexeme
+                        ))
+                        .at(name.span()));
+                    }
+                }
+                *depth = self.resolve_local(name);
+                Ok(())
+            }
+            Expr::Assign { name, value, depth } => {
+                self.resolve_expr(value)?;
+                *depth = self.resolve_local(name);
+                Ok(())
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.resolve_expr(left)?;
+                self.resolve_expr(right)
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition)?;
+                self.resolve_expr(then_branch)?;
+                self.resolve_expr(else_branch)
+            }
+            Expr::Grouping { expression } => self.resolve_expr(expression),
+            Expr::Literal { .. } => Ok(()),
+            Expr::Unary { operand, .. } => self.resolve_expr(operand),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.resolve_expr(callee)?;
+                for argument in arguments {
+                    self.resolve_expr(argument)?;
+                }
+                Ok(())
+            }
+            Expr::Get { object, .. } => self.resolve_expr(object),
+            Expr::CompoundAssign { target, value, .. } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(target)
+            }
+            Expr::Increment { target, .. } => self.resolve_expr(target),
+            Expr::Set { object, value, .. } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+                Ok(())
+            }
+            Expr::Spread { expression, .. } => self.resolve_expr(expression),
+            Expr::Lambda { params, body, .. } => {
+                self.resolve_function(params, body, FunctionType::Function)
+            }
+            Expr::Interpolation { parts } => {
+                for part in parts {
+                    self.resolve_expr(part)?;
+                }
+                Ok(())
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key)?;
+                    self.resolve_expr(value)?;
+                }
+                Ok(())
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)
+            }
+            Expr::This { keyword } => {
+                if self.current_class == ClassType::None {
+                    return Err(LoxError::ResolveError(
+                        "Can't use 'this' outside of a class.".to_string(),
+                    )
+                    .at(keyword.span()));
+                }
+                Ok(())
+            }
+            Expr::Super { keyword, .. } => match self.current_class {
+                ClassType::None => Err(LoxError::ResolveError(
+                    "Can't use 'super' outside of a class.".to_string(),
+                )
+                .at(keyword.span())),
+                ClassType::Class => Err(LoxError::ResolveError(
+                    "Can't use 'super' in a class with no superclass.".to_string(),
+                )
+                .at(keyword.span())),
+                ClassType::Subclass => Ok(()),
+            },
+        }
+    }
+
+    /// 関数の本体を新しいスコープで解析します。
+    ///
+    /// `Evaluator` と同様に、パラメータと本体のステートメントは同じスコープに置かれます。
+    /// メソッドの場合は同じスコープに `this` も定義されます。
+    /// デフォルト値の式は、それより前のパラメータが定義された関数のスコープで解析されます。
+    ///
+    /// # 引数
+    /// - `params`: パラメータのリスト。
+    /// - `body`: 関数の本体。
+    /// - `kind`: 関数の種類。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: `LoxError`。
+    fn resolve_function(
+        &mut self,
+        params: &mut [Parameter],
+        body: &mut [Stmt],
+        kind: FunctionType,
+    ) -> Result<(), LoxError> {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+
+        self.begin_scope();
+        if matches!(kind, FunctionType::Method | FunctionType::Initializer) {
+            self.define_name("this");
+        }
+        for param in params {
+            if let Some(default) = &mut param.default {
+                self.resolve_expr(default)?;
+            }
+            self.declare(&param.name)?;
+            self.define(&param.name);
+        }
+        self.resolve(body)?;
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        Ok(())
+    }
+
+    /// 変数が定義されているスコープまでの距離を計算します。
+    ///
+    /// # 引数
+    /// - `name`: 変数名のトークン。
+    ///
+    /// # 戻り値
+    /// - ローカル変数の場合は `Some(距離)`、見つからない場合（グローバル変数）は `None`。
+    fn resolve_local(&self, name: &Token) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(&name.lexeme))
+    }
+
+    /// 新しいスコープを開始します。
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// 現在のスコープを終了します。
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// 現在のスコープに変数を宣言します（初期化は未完了として扱います）。
+    ///
+    /// # 引数
+    /// - `name`: 変数名のトークン。
+    ///
+    /// # 戻り値
+    /// - 成功時: `Ok(())`。
+    /// - 失敗時: 同じスコープに同名の変数が既に存在する場合の `LoxError`。
+    fn declare(&mut self, name: &Token) -> Result<(), LoxError> {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&name.lexeme) {
+                return Err(LoxError::ResolveError(format!(
+                    "Already a variable named '{}' in this scope.",
+                    name.lexeme
+                ))
+                .at(name.span()));
+            }
+            scope.insert(name.lexeme.clone(), false);
+        }
+        Ok(())
+    }
+
+    /// 現在のスコープで変数の初期化が完了したことを記録します。
+    ///
+    /// # 引数
+    /// - `name`: 変数名のトークン。
+    fn define(&mut self, name: &Token) {
+        self.define_name(&name.lexeme);
+    }
+
+    /// 現在のスコープに名前を初期化済みとして定義します。
+    ///
+    /// # 引数
+    /// - `name`: 変数名。
+    fn define_name(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+}
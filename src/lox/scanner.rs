@@ -1,31 +1,63 @@
 use crate::lox::error::LoxError;
-use crate::lox::token::Token;
+use crate::lox::token::{Span, Token};
 use crate::lox::token_type::{LiteralValue, TokenType};
+use std::rc::Rc;
+
+/// ファイル名が指定されていない場合に使用するソースコードの名前
+const DEFAULT_FILE_NAME: &str = "<script>";
 
 /// 字句解析器（Scanner）
+///
+/// ソースコードは文字（`char`）単位で扱うため、位置や列番号はバイトではなく文字で数える。
 pub struct Scanner {
-    source: String,
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    /// 現在のトークンの開始位置の行番号
+    start_line: usize,
+    file: Rc<str>,
 }
 
 impl Scanner {
     /// 新しい `Scanner` の生成
     ///
+    /// ファイル名には `<script>` を使用する。
+    ///
     /// # 引数
     /// - `source`: ソースコードの文字列
     ///
     /// # 戻り値
     /// 新しい `Scanner` インスタンス
     pub fn new(source: &str) -> Self {
+        Self::with_file(source, DEFAULT_FILE_NAME)
+    }
+
+    /// ファイル名を指定した `Scanner` の生成
+    ///
+    /// 先頭の UTF-8 BOM は取り除き、改行コード CRLF は LF に統一する。
+    ///
+    /// # 引数
+    /// - `source`: ソースコードの文字列
+    /// - `file`: トークンやエラーの位置に表示するファイル名
+    ///
+    /// # 戻り値
+    /// 新しい `Scanner` インスタンス
+    pub fn with_file(source: &str, file: &str) -> Self {
         Scanner {
-            source: source.to_string(),
+            source: source
+                .strip_prefix('\u{feff}')
+                .unwrap_or(source)
+                .replace("\r\n", "\n")
+                .chars()
+                .collect(),
             tokens: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            start_line: 1,
+            file: file.into(),
         }
     }
 
@@ -38,12 +70,30 @@ impl Scanner {
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, LoxError> {
         while !self.is_at_end() {
             self.start = self.current;
-            self.scan_token()?; // 各トークンをスキャン
+            self.start_line = self.line;
+            // エラーの位置はトークンの開始位置とする（文字列補間の解析中に `start` は更新される）
+            let (start, line, column) = (self.start, self.start_line, self.column());
+            self.scan_token().map_err(|err| {
+                err.at(Span {
+                    file: Rc::clone(&self.file),
+                    line,
+                    column,
+                    length: self.current - start,
+                })
+            })?; // 各トークンをスキャン
         }
 
         // 終端トークンを追加
-        self.tokens
-            .push(Token::new(TokenType::Eof, "".to_string(), None, self.line));
+        self.start = self.current;
+        self.start_line = self.line;
+        self.tokens.push(Token::new(
+            TokenType::Eof,
+            "".to_string(),
+            None,
+            self.start_line,
+            self.column(),
+            Rc::clone(&self.file),
+        ));
 
         Ok(self.tokens.clone())
     }
@@ -61,13 +111,71 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '.' => {
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    self.advance();
+                    self.advance();
+                    self.add_token(TokenType::Ellipsis);
+                } else {
+                    self.add_token(TokenType::Dot);
+                }
+            }
+            '-' => {
+                let token_type = if self.match_char('-') {
+                    TokenType::MinusMinus
+                } else if self.match_char('=') {
+                    TokenType::MinusEqual
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(token_type);
+            }
+            '+' => {
+                let token_type = if self.match_char('+') {
+                    TokenType::PlusPlus
+                } else if self.match_char('=') {
+                    TokenType::PlusEqual
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token(token_type);
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
-            '%' => self.add_token(TokenType::Percent),
+            ':' => self.add_token(TokenType::Colon),
+            '?' => self.add_token(TokenType::Question),
+            '*' => {
+                let token_type = if self.match_char('*') {
+                    TokenType::StarStar
+                } else if self.match_char('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(token_type);
+            }
+            '%' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::PercentEqual
+                } else {
+                    TokenType::Percent
+                };
+                self.add_token(token_type);
+            }
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '~' => {
+                // `//` は行コメントのため、整数除算には `~/` を使用する
+                let token_type = if self.match_char('/') {
+                    TokenType::TildeSlash
+                } else {
+                    TokenType::Tilde
+                };
+                self.add_token(token_type);
+            }
             '!' => {
                 let token_type = if self.match_char('=') {
                     TokenType::BangEqual
@@ -87,6 +195,8 @@ impl Scanner {
             '<' => {
                 let token_type = if self.match_char('=') {
                     TokenType::LessEqual
+                } else if self.match_char('<') {
+                    TokenType::LessLess
                 } else {
                     TokenType::Less
                 };
@@ -95,6 +205,8 @@ impl Scanner {
             '>' => {
                 let token_type = if self.match_char('=') {
                     TokenType::GreaterEqual
+                } else if self.match_char('>') {
+                    TokenType::GreaterGreater
                 } else {
                     TokenType::Greater
                 };
@@ -109,17 +221,27 @@ impl Scanner {
                 } else if self.match_char('*') {
                     // ブロックコメントのスキップ
                     self.skip_block_comment()?;
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::SlashEqual);
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
-            '"' => self.string()?,
+            '"' => {
+                let triple = self.peek() == '"' && self.peek_next() == '"';
+                if triple {
+                    self.current += 2;
+                }
+                self.string(triple)?;
+            }
             ' ' | '\r' | '\t' => {} // 空白のスキップ
             '\n' => self.line += 1, // 行番号のインクリメント
             _ => {
-                if c.is_ascii_digit() {
-                    self.number();
-                } else if c.is_ascii_alphanumeric() || c == '_' {
+                if c == 'r' && self.peek() == '"' {
+                    self.raw_string()?;
+                } else if c.is_ascii_digit() {
+                    self.number()?;
+                } else if is_identifier_start(c) {
                     self.identifier();
                 } else {
                     return Err(LoxError::UnexpectedCharacter(c));
@@ -131,7 +253,7 @@ impl Scanner {
 
     /// 現在位置の文字を取得して次に進む
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap_or('\0');
+        let c = self.source.get(self.current).copied().unwrap_or('\0');
         self.current += 1;
         c
     }
@@ -141,7 +263,7 @@ impl Scanner {
         if self.is_at_end() {
             return false;
         }
-        if self.source.chars().nth(self.current).unwrap_or('\0') != expected {
+        if self.source[self.current] != expected {
             return false;
         }
         self.current += 1;
@@ -150,71 +272,404 @@ impl Scanner {
 
     /// トークンの追加
     fn add_token(&mut self, token_type: TokenType) {
-        let text = self.source[self.start..self.current].to_string();
-        self.tokens
-            .push(Token::new(token_type, text, None, self.line));
+        let text = self.source[self.start..self.current].iter().collect();
+        let column = self.column();
+        self.tokens.push(Token::new(
+            token_type,
+            text,
+            None,
+            self.start_line,
+            column,
+            Rc::clone(&self.file),
+        ));
     }
 
     /// リテラルを持つトークンの追加
     fn add_token_with_literal(&mut self, token_type: TokenType, literal: LiteralValue) {
-        let text = self.source[self.start..self.current].to_string();
-        self.tokens
-            .push(Token::new(token_type, text, Some(literal), self.line));
+        let text = self.source[self.start..self.current].iter().collect();
+        let column = self.column();
+        self.tokens.push(Token::new(
+            token_type,
+            text,
+            Some(literal),
+            self.start_line,
+            column,
+            Rc::clone(&self.file),
+        ));
+    }
+
+    /// 現在のトークンの開始位置の列番号（1始まり、文字単位）の計算
+    fn column(&self) -> usize {
+        self.source[..self.start]
+            .iter()
+            .rev()
+            .take_while(|&&c| c != '\n')
+            .count()
+            + 1
     }
 
     /// 文字列リテラルの解析
-    fn string(&mut self) -> Result<(), LoxError> {
-        while !self.is_at_end() && self.peek() != '"' {
-            if self.peek() == '\n' {
-                self.line += 1;
+    ///
+    /// `${expr}` による文字列補間を含む場合は、`${` の直前までの各部分を
+    /// `StringInterpolation` トークンとして、埋め込み式のトークンをその後に続けて追加し、
+    /// 最後の部分を `StringLit` トークンとして追加する。
+    ///
+    /// `triple` が `true` の場合は `"""` で囲まれた複数行文字列として解析し、
+    /// 開始直後の改行と終了直前の空白だけの行を取り除いたうえで、共通のインデントを各行から取り除く。
+    fn string(&mut self, triple: bool) -> Result<(), LoxError> {
+        let indent = if triple { self.multiline_indent() } else { 0 };
+        let mut value = String::new();
+        // 複数行文字列で、末尾の空白だけの行が始まる位置（最後の改行の位置）
+        let mut trailing_blank_line: Option<usize> = None;
+
+        if triple {
+            // 開始の `"""` と同じ行に空白しかない場合、その行は内容に含めない
+            let rest_is_blank = self.source[self.current..]
+                .iter()
+                .copied()
+                .take_while(|&c| c != '\n')
+                .all(|c| c == ' ' || c == '\t' || c == '\r');
+            if rest_is_blank && !self.is_triple_quote() {
+                while self.peek() != '\n' && !self.is_at_end() {
+                    self.advance();
+                }
+                if self.match_char('\n') {
+                    self.line += 1;
+                    self.skip_indent(indent);
+                }
             }
-            self.advance();
         }
 
-        if self.is_at_end() {
-            return Err(LoxError::UnterminatedString(format!(
-                "Unterminated string literal at line {}.",
-                self.line
-            )));
+        loop {
+            if self.is_at_end() {
+                return Err(LoxError::UnterminatedString(
+                    "Unterminated string literal.".to_string(),
+                ));
+            }
+
+            if triple && self.is_triple_quote() {
+                self.current += 3;
+                break;
+            }
+
+            match self.advance() {
+                '"' if !triple => break,
+                '\\' => {
+                    value.push(self.escape_sequence()?);
+                    trailing_blank_line = None;
+                }
+                '$' if self.peek() == '{' => {
+                    self.advance();
+                    self.add_token_with_literal(
+                        TokenType::StringInterpolation,
+                        LiteralValue::String(std::mem::take(&mut value)),
+                    );
+                    self.interpolation()?;
+                    self.start = self.current;
+                    self.start_line = self.line;
+                    trailing_blank_line = None;
+                }
+                '\n' => {
+                    self.line += 1;
+                    trailing_blank_line = Some(value.len());
+                    value.push('\n');
+                    if triple {
+                        self.skip_indent(indent);
+                    }
+                }
+                c => {
+                    if c != ' ' && c != '\t' && c != '\r' {
+                        trailing_blank_line = None;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+
+        if let (true, Some(position)) = (triple, trailing_blank_line) {
+            value.truncate(position);
         }
 
+        self.add_token_with_literal(TokenType::StringLit, LiteralValue::String(value));
+        Ok(())
+    }
+
+    /// 生文字列リテラル `r"..."` の解析
+    ///
+    /// エスケープシーケンスや文字列補間は解釈せず、引用符の間の文字をそのまま値とする。
+    fn raw_string(&mut self) -> Result<(), LoxError> {
+        // 開始の `"` を消費
         self.advance();
-        let value = self.source[self.start + 1..self.current - 1].to_string();
+        let mut value = String::new();
+
+        loop {
+            if self.is_at_end() {
+                return Err(LoxError::UnterminatedString(
+                    "Unterminated string literal.".to_string(),
+                ));
+            }
+            match self.advance() {
+                '"' => break,
+                c => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+
         self.add_token_with_literal(TokenType::StringLit, LiteralValue::String(value));
         Ok(())
     }
 
-    /// 数字の解析
-    fn number(&mut self) {
-        while !self.is_at_end() && self.peek().is_ascii_digit() {
-            self.advance();
+    /// エスケープシーケンスの解析
+    ///
+    /// `\` の直後から呼び出し、対応する文字を返す。
+    /// 対応するエスケープは `\n`、`\t`、`\r`、`\0`、`\"`、`\\`、`\$`、`\u{XXXX}`。
+    fn escape_sequence(&mut self) -> Result<char, LoxError> {
+        if self.is_at_end() {
+            return Err(LoxError::UnterminatedString(
+                "Unterminated string literal.".to_string(),
+            ));
         }
 
-        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+        match self.advance() {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            '$' => Ok('$'),
+            'u' => {
+                if !self.match_char('{') {
+                    return Err(LoxError::InvalidEscapeSequence(
+                        "Expected '{' after '\\u'.".to_string(),
+                    ));
+                }
+                let mut digits = String::new();
+                while self.peek().is_ascii_hexdigit() {
+                    digits.push(self.advance());
+                }
+                if !self.match_char('}') || digits.is_empty() || digits.len() > 6 {
+                    return Err(LoxError::InvalidEscapeSequence(format!(
+                        "Malformed unicode escape '\\u{{{}'.",
+                        digits
+                    )));
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        LoxError::InvalidEscapeSequence(format!(
+                            "Invalid unicode code point '\\u{{{}}}'.",
+                            digits
+                        ))
+                    })
+            }
+            c => Err(LoxError::InvalidEscapeSequence(format!(
+                "Unknown escape sequence '\\{}'.",
+                c
+            ))),
+        }
+    }
+
+    /// 現在位置から `"""` が始まるかの判定
+    fn is_triple_quote(&self) -> bool {
+        self.source[self.current..]
+            .iter()
+            .copied()
+            .take(3)
+            .eq("\"\"\"".chars())
+    }
+
+    /// 複数行文字列の共通インデントの計算
+    ///
+    /// 開始の `"""` の後の行から終了の `"""` までを先読みし、空白だけの行を除いた各行の
+    /// 先頭の空白の数の最小値を返す。
+    fn multiline_indent(&self) -> usize {
+        let content: String = self.source[self.current..].iter().collect();
+        let content = match content.find("\"\"\"") {
+            Some(end) => &content[..end],
+            None => &content[..],
+        };
+        content
+            .split('\n')
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.chars().take_while(|&c| c == ' ' || c == '\t').count())
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// 行頭の空白を最大 `indent` 文字まで読み飛ばす
+    fn skip_indent(&mut self, indent: usize) {
+        let mut skipped = 0;
+        while skipped < indent && (self.peek() == ' ' || self.peek() == '\t') {
             self.advance();
-            while self.peek().is_ascii_digit() {
-                self.advance();
+            skipped += 1;
+        }
+    }
+
+    /// 文字列補間の埋め込み式の解析
+    ///
+    /// 対応する `}` が現れるまでトークンをスキャンする。埋め込み式の中の `{ }` や
+    /// 文字列リテラル（さらに補間を含むものを含む）は通常どおりスキャンされる。
+    fn interpolation(&mut self) -> Result<(), LoxError> {
+        let unterminated =
+            || LoxError::UnterminatedString("Unterminated string interpolation.".to_string());
+        let mut depth = 0;
+
+        loop {
+            if self.is_at_end() {
+                return Err(unterminated());
+            }
+
+            self.start = self.current;
+
+            self.start_line = self.line;
+            let token_count = self.tokens.len();
+            // 閉じられていない補間の中では外側の `"` が新しい文字列の開始として扱われるため、
+            // 終端のない文字列は補間が閉じられていないものとして報告する
+            self.scan_token().map_err(|err| match err {
+                LoxError::UnterminatedString(_) => unterminated(),
+                err => err,
+            })?;
+            if self.tokens.len() == token_count {
+                continue; // 空白やコメント
+            }
+
+            match self.tokens[token_count].token_type {
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace if depth == 0 => {
+                    // 補間を閉じる `}` はトークンとして残さない
+                    self.tokens.pop();
+                    return Ok(());
+                }
+                TokenType::RightBrace => depth -= 1,
+                _ => {}
             }
         }
+    }
+
+    /// 数字の解析
+    ///
+    /// 10進数（小数部と指数部を含む）に加えて、`0x`（16進数）、`0b`（2進数）、`0o`（8進数）の
+    /// 接頭辞を持つ整数を受け付ける。数字の間には区切り文字 `_` を置くことができる。
+    fn number(&mut self) -> Result<(), LoxError> {
+        let first = self.source[self.start];
+        let radix = match (first, self.peek()) {
+            ('0', 'x' | 'X') => Some((16, "hexadecimal")),
+            ('0', 'b' | 'B') => Some((2, "binary")),
+            ('0', 'o' | 'O') => Some((8, "octal")),
+            _ => None,
+        };
+
+        let value = if let Some((radix, name)) = radix {
+            let prefix = self.advance();
+            let mut digits = String::new();
+            self.digits(radix, &mut digits)?;
+            if digits.is_empty() {
+                return Err(LoxError::MalformedNumber(format!(
+                    "'0{}' must be followed by {} digits.",
+                    prefix, name
+                )));
+            }
+            if self.peek().is_ascii_alphanumeric() {
+                return Err(LoxError::MalformedNumber(format!(
+                    "Invalid digit '{}' in {} literal.",
+                    self.peek(),
+                    name
+                )));
+            }
+            u64::from_str_radix(&digits, radix).map_err(|_| {
+                LoxError::MalformedNumber(format!(
+                    "{} literal '0{}{}' is too large.",
+                    name, prefix, digits
+                ))
+            })? as f64
+        } else {
+            let mut text = first.to_string();
+            self.digits(10, &mut text)?;
+
+            // 小数部
+            if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+                text.push(self.advance());
+                self.digits(10, &mut text)?;
+            }
+
+            // 指数部
+            if self.peek() == 'e' || self.peek() == 'E' {
+                text.push(self.advance());
+                if self.peek() == '+' || self.peek() == '-' {
+                    text.push(self.advance());
+                }
+                if !self.peek().is_ascii_digit() {
+                    return Err(LoxError::MalformedNumber(format!(
+                        "Exponent of '{}' has no digits.",
+                        text
+                    )));
+                }
+                self.digits(10, &mut text)?;
+            }
+
+            if self.peek().is_alphabetic() {
+                return Err(LoxError::MalformedNumber(format!(
+                    "Unexpected character '{}' after number '{}'.",
+                    self.peek(),
+                    text
+                )));
+            }
+
+            text.parse()
+                .map_err(|_| LoxError::MalformedNumber(format!("'{}'.", text)))?
+        };
 
-        let value: f64 = self.source[self.start..self.current]
-            .parse()
-            .expect("Failed to parse number.");
         self.add_token_with_literal(TokenType::Number, LiteralValue::Number(value));
+        Ok(())
+    }
+
+    /// 指定した基数の数字の並びを読み取り、区切り文字 `_` を除いて `text` に追加する
+    ///
+    /// `_` は数字と数字の間にのみ置くことができる。
+    fn digits(&mut self, radix: u32, text: &mut String) -> Result<(), LoxError> {
+        let mut previous_is_digit = text.chars().last().is_some_and(|c| c.is_digit(radix));
+        loop {
+            let c = self.peek();
+            if c.is_digit(radix) {
+                text.push(self.advance());
+                previous_is_digit = true;
+            } else if c == '_' {
+                if !previous_is_digit || !self.peek_next().is_digit(radix) {
+                    return Err(LoxError::MalformedNumber(
+                        "'_' must separate digits.".to_string(),
+                    ));
+                }
+                self.advance();
+                previous_is_digit = false;
+            } else {
+                return Ok(());
+            }
+        }
     }
 
     /// 識別子の解析
     fn identifier(&mut self) {
-        while !self.is_at_end() && (self.peek().is_ascii_alphanumeric() || self.peek() == '_') {
+        while !self.is_at_end() && is_identifier_continue(self.peek()) {
             self.advance();
         }
 
-        let text = self.source[self.start..self.current].to_string();
+        let text: String = self.source[self.start..self.current].iter().collect();
         let token_type = match text.as_str() {
             "and" => TokenType::And,
+            "break" => TokenType::Break,
+            "catch" => TokenType::Catch,
             "class" => TokenType::Class,
+            "continue" => TokenType::Continue,
             "else" => TokenType::Else,
             "false" => TokenType::False,
+            "finally" => TokenType::Finally,
             "for" => TokenType::For,
             "fun" => TokenType::Fun,
             "if" => TokenType::If,
@@ -224,7 +679,9 @@ impl Scanner {
             "return" => TokenType::Return,
             "super" => TokenType::Super,
             "this" => TokenType::This,
+            "throw" => TokenType::Throw,
             "true" => TokenType::True,
+            "try" => TokenType::Try,
             "var" => TokenType::Var,
             "while" => TokenType::While,
             _ => TokenType::Identifier,
@@ -237,7 +694,7 @@ impl Scanner {
         if self.is_at_end() {
             '\0'
         } else {
-            self.source.chars().nth(self.current).unwrap_or('\0')
+            self.source[self.current]
         }
     }
 
@@ -246,7 +703,7 @@ impl Scanner {
         if self.current + 1 >= self.source.len() {
             '\0'
         } else {
-            self.source.chars().nth(self.current + 1).unwrap_or('\0')
+            self.source[self.current + 1]
         }
     }
 
@@ -272,11 +729,24 @@ impl Scanner {
         }
 
         if depth > 0 {
-            return Err(LoxError::UnterminatedString(format!(
-                "Unterminated string literal at line {}.",
-                self.line
-            )));
+            return Err(LoxError::UnterminatedString(
+                "Unterminated string literal.".to_string(),
+            ));
         }
         Ok(())
     }
 }
+
+/// 識別子の先頭に使用できる文字かの判定
+///
+/// Unicode の `XID_Start`（日本語の漢字・かななどを含む）と `_` を受け付ける。
+fn is_identifier_start(c: char) -> bool {
+    unicode_ident::is_xid_start(c) || c == '_'
+}
+
+/// 識別子の2文字目以降に使用できる文字かの判定
+///
+/// Unicode の `XID_Continue`（結合文字や数字、`_` を含む）を受け付ける。
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
@@ -1,8 +1,32 @@
 use crate::lox::token_type::{LiteralValue, TokenType};
+use std::fmt;
+use std::rc::Rc;
+
+/// `Span` は、ソースコード上の位置と範囲を表す構造体です。
+///
+/// エラーの発生箇所を示すために使用し、`file:line:col` の形式で表示されます。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// ソースコードのファイル名です。
+    pub file: Rc<str>,
+    /// 開始位置の行番号です（1始まり）。
+    pub line: usize,
+    /// 開始位置の列番号です（1始まり、文字単位）。
+    pub column: usize,
+    /// 範囲の長さ（文字数）です。
+    pub length: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
 
 /// `Token` は、Lox 言語のトークンを表す構造体です。
 ///
-/// 各トークンは、トークンの種類、元の文字列、オプションのリテラル値、および行番号を保持します。
+/// 各トークンは、トークンの種類、元の文字列、オプションのリテラル値、行番号、列番号、
+/// およびトークンが現れたファイル名を保持します。
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     /// トークンの種類を示します（例: Identifier, Number, Keyword など）。
@@ -14,6 +38,11 @@ pub struct Token {
     pub literal: Option<LiteralValue>,
     /// トークンが現れたソースコードの行番号です。
     pub line: usize,
+    /// トークンが現れた行内の列番号です（1始まり）。
+    /// マルチバイト文字を含む行でも正しく位置を示せるよう、バイトではなく文字単位で数えます。
+    pub column: usize,
+    /// トークンが現れたソースコードのファイル名です。
+    pub file: Rc<str>,
 }
 
 impl Token {
@@ -24,6 +53,8 @@ impl Token {
     /// - `lexeme`: トークンの元の文字列を指定します。
     /// - `literal`: トークンに関連付けられたリテラル値を指定します（存在しない場合は `None` を指定）。
     /// - `line`: トークンが現れたソースコードの行番号を指定します。
+    /// - `column`: トークンが現れた行内の列番号（文字単位）を指定します。
+    /// - `file`: トークンが現れたソースコードのファイル名を指定します。
     ///
     /// # 戻り値
     /// 作成された新しい `Token` インスタンスを返します。
@@ -37,7 +68,9 @@ impl Token {
     ///     TokenType::Identifier,
     ///     "example".to_string(),
     ///     None,
-    ///     1
+    ///     1,
+    ///     1,
+    ///     "script.lox".into(),
     /// );
     /// println!("{:?}", token);
     /// ```
@@ -46,12 +79,29 @@ impl Token {
         lexeme: String,
         literal: Option<LiteralValue>,
         line: usize,
+        column: usize,
+        file: Rc<str>,
     ) -> Self {
         Token {
             token_type,
             lexeme,
             literal,
             line,
+            column,
+            file,
+        }
+    }
+
+    /// トークンの位置と範囲を表す `Span` を返します。
+    ///
+    /// # 戻り値
+    /// トークンの開始位置と字句の長さ（文字数）を持つ `Span`。
+    pub fn span(&self) -> Span {
+        Span {
+            file: Rc::clone(&self.file),
+            line: self.line,
+            column: self.column,
+            length: self.lexeme.chars().count(),
         }
     }
 }
@@ -14,10 +14,16 @@ pub enum TokenType {
     LeftBrace,
     /// `}` トークン
     RightBrace,
+    /// `[` トークン
+    LeftBracket,
+    /// `]` トークン
+    RightBracket,
     /// `,` トークン
     Comma,
     /// `.` トークン
     Dot,
+    /// `...` トークン
+    Ellipsis,
     /// `-` トークン
     Minus,
     /// `+` トークン
@@ -30,6 +36,16 @@ pub enum TokenType {
     Star,
     /// `%` トークン
     Percent,
+    /// `&` トークン
+    Ampersand,
+    /// `|` トークン
+    Pipe,
+    /// `^` トークン
+    Caret,
+    /// `:` トークン
+    Colon,
+    /// `?` トークン
+    Question,
 
     // One or two character tokens
     /// `!` トークン
@@ -44,6 +60,30 @@ pub enum TokenType {
     Greater,
     /// `>=` トークン
     GreaterEqual,
+    /// `>>` トークン
+    GreaterGreater,
+    /// `+=` トークン
+    PlusEqual,
+    /// `-=` トークン
+    MinusEqual,
+    /// `*=` トークン
+    StarEqual,
+    /// `/=` トークン
+    SlashEqual,
+    /// `%=` トークン
+    PercentEqual,
+    /// `++` トークン
+    PlusPlus,
+    /// `--` トークン
+    MinusMinus,
+    /// `<<` トークン
+    LessLess,
+    /// `**` トークン
+    StarStar,
+    /// `~` トークン
+    Tilde,
+    /// `~/` トークン（整数除算）
+    TildeSlash,
     /// `<` トークン
     Less,
     /// `<=` トークン
@@ -54,18 +94,28 @@ pub enum TokenType {
     Identifier,
     /// 文字列リテラルトークン
     StringLit,
+    /// 文字列補間 `${` の直前までの文字列部分。後に埋め込み式のトークンが続きます。
+    StringInterpolation,
     /// 数値リテラルトークン
     Number,
 
     // Keywords
     /// `and` キーワード
     And,
+    /// `break` キーワード
+    Break,
+    /// `catch` キーワード
+    Catch,
     /// `class` キーワード
     Class,
+    /// `continue` キーワード
+    Continue,
     /// `else` キーワード
     Else,
     /// `false` キーワード
     False,
+    /// `finally` キーワード
+    Finally,
     /// `fun` キーワード
     Fun,
     /// `for` キーワード
@@ -84,8 +134,12 @@ pub enum TokenType {
     Super,
     /// `this` キーワード
     This,
+    /// `throw` キーワード
+    Throw,
     /// `true` キーワード
     True,
+    /// `try` キーワード
+    Try,
     /// `var` キーワード
     Var,
     /// `while` キーワード
@@ -1,46 +1,127 @@
+use crate::lox::diagnostic::Diagnostic;
 use crate::lox::error::LoxError;
 use lox::evaluator::EvalResult;
 use std::env;
 use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 mod lox;
 
+/// コマンドライン引数の使い方。
+const USAGE: &str = "Usage: lox [--strict] [--color=never|always|auto] [script]";
+
 /// エントリーポイント関数。
 ///
 /// 引数が指定されていればスクリプトファイルを実行し、
 /// 指定されていなければ対話型プロンプトを起動します。
+/// `--strict` を指定すると、条件式に真偽値以外を許可しない厳格モードでスクリプトやプロンプトを実行します。
+/// `--color=never|always|auto` でエラー表示の色付けを切り替えます（既定は `auto`）。
 fn main() -> Result<(), LoxError> {
-    let args: Vec<String> = env::args().collect();
-    match args.as_slice() {
-        [_, script] => run_file(script)?,
-        [_] => run_prompt()?,
-        _ => {
-            std::process::exit(64);
+    let mut strict = false;
+    let mut color = color_choice("auto");
+    let mut script = None;
+    for arg in env::args().skip(1) {
+        if arg == "--strict" {
+            strict = true;
+        } else if let Some(mode) = arg.strip_prefix("--color=") {
+            color = color_choice(mode);
+            if color.is_none() {
+                usage_error(&format!("invalid value '{}' for '--color'", mode));
+            }
+        } else if script.is_none() && !arg.starts_with("--") {
+            script = Some(arg);
+        } else {
+            usage_error(&format!("unexpected argument '{}'", arg));
+        }
+    }
+
+    match (script, color) {
+        (Some(script), Some(color)) => {
+            // エラーは `run_file` で表示済みのため、終了コードのみを返す
+            if run_file(&script, strict, color).is_err() {
+                std::process::exit(65);
+            }
         }
+        (None, Some(color)) => run_prompt(strict, color)?,
+        (_, None) => unreachable!("invalid '--color' values are rejected while parsing arguments"),
     }
     Ok(())
 }
 
+/// コマンドライン引数の誤りと使い方を標準エラー出力に表示し、終了コード 64 で終了します。
+///
+/// # 引数
+/// - `message`: 誤りの内容。
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!("{}", USAGE);
+    std::process::exit(64);
+}
+
+/// `--color` の指定をエラー出力の色付けの設定に変換します。
+///
+/// `auto` の場合は、標準エラー出力が端末であれば環境変数（`NO_COLOR` など）に従って色付けし、
+/// ファイルやパイプへの出力では色付けしません。
+///
+/// # 引数
+/// - `mode`: `never`、`always`、`auto` のいずれか。
+///
+/// # 戻り値
+/// 対応する `ColorChoice`。不明な指定の場合は `None`。
+fn color_choice(mode: &str) -> Option<ColorChoice> {
+    match mode {
+        "never" => Some(ColorChoice::Never),
+        "always" => Some(ColorChoice::Always),
+        "auto" if io::stderr().is_terminal() => Some(ColorChoice::Auto),
+        "auto" => Some(ColorChoice::Never),
+        _ => None,
+    }
+}
+
+/// エラーをソースコードの抜粋付きで標準エラー出力に表示します。
+///
+/// # 引数
+/// - `err`: 表示するエラー。
+/// - `source`: エラーが発生したソースコード。
+/// - `color`: 色付けの設定。
+fn report(err: &LoxError, source: &str, color: ColorChoice) {
+    let mut stderr = StandardStream::stderr(color);
+    for diagnostic in Diagnostic::collect(err) {
+        if diagnostic.render(source, &mut stderr).is_err() {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    }
+}
+
 /// 指定されたスクリプトファイルを実行します。
 ///
 /// # 引数
 /// - `path`: 実行するスクリプトファイルのパス。
+/// - `strict`: 条件式の厳格モードを有効にするかどうか。
+/// - `color`: エラー表示の色付けの設定。
 ///
 /// # エラー
 /// ファイルが見つからない場合、または実行中にエラーが発生した場合に `LoxError` を返します。
-fn run_file(path: &str) -> Result<(), LoxError> {
-    let source = fs::read_to_string(path).map_err(|_| LoxError::FileNotFound(path.to_string()))?;
+fn run_file(path: &str, strict: bool, color: ColorChoice) -> Result<(), LoxError> {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) => {
+            let err = LoxError::FileNotFound(path.to_string());
+            report(&err, "", color);
+            return Err(err);
+        }
+    };
 
     // 実行して結果を出力
-    match run(&source) {
+    match run(&source, path, strict) {
         Ok(output) => {
             println!("{}", output); // 成功時に出力を表示
             Ok(())
         }
         Err(err) => {
-            eprintln!("Error: {}", err); // エラーを表示
+            report(&err, &source, color); // エラーを表示
             Err(err)
         }
     }
@@ -51,6 +132,10 @@ fn run_file(path: &str) -> Result<(), LoxError> {
 /// ユーザーが入力したコードを1行ずつ評価します。
 /// 各行のコードは保持された`Evaluator`インスタンスによって評価されるため、変数やスコープの状態が維持されます。
 ///
+/// # 引数
+/// - `strict`: 条件式の厳格モードを有効にするかどうか。
+/// - `color`: エラー表示の色付けの設定。
+///
 /// # 戻り値
 /// - `Ok(())`: プロンプトが正常に終了した場合。
 /// - `Err(LoxError)`: 入力または出力処理中にエラーが発生した場合。
@@ -66,10 +151,11 @@ fn run_file(path: &str) -> Result<(), LoxError> {
 /// // > print x;          // 変数の表示: 10
 /// // >                  // 空行で終了
 /// ```
-fn run_prompt() -> Result<(), LoxError> {
+fn run_prompt(strict: bool, color: ColorChoice) -> Result<(), LoxError> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut evaluator = lox::evaluator::Evaluator::new(); // プロンプト全体でEvaluatorを保持
+    evaluator.set_strict_conditions(strict);
     a();
     loop {
         write!(stdout, "> ")
@@ -85,7 +171,7 @@ fn run_prompt() -> Result<(), LoxError> {
 
         match run_with_evaluator(&line, &mut evaluator) {
             Ok(output) => println!("{}", output),
-            Err(err) => eprintln!("Error: {}", err),
+            Err(err) => report(&err, &line, color),
         }
     }
 
@@ -94,7 +180,7 @@ fn run_prompt() -> Result<(), LoxError> {
 
 /// 指定されたソースコードを指定された`Evaluator`で評価します。
 ///
-/// スキャナー、パーサーを順に呼び出し、構文解析を行った後、`Evaluator`によってコードを実行します。
+/// スキャナー、パーサー、リゾルバを順に呼び出し、構文解析と変数解決を行った後、`Evaluator`によってコードを実行します。
 /// `Evaluator`は呼び出し元から提供されるため、スコープや変数の状態が維持されます。
 ///
 /// # 引数
@@ -103,7 +189,7 @@ fn run_prompt() -> Result<(), LoxError> {
 ///
 /// # 戻り値
 /// - `Ok(String)`: 評価が成功した場合、実行結果を文字列で返します。
-/// - `Err(LoxError)`: スキャナー、パーサー、または評価中にエラーが発生した場合。
+/// - `Err(LoxError)`: スキャナー、パーサー、リゾルバ、または評価中にエラーが発生した場合。
 ///
 /// # 使用例
 /// ```
@@ -118,7 +204,7 @@ fn run_with_evaluator(
     source: &str,
     evaluator: &mut lox::evaluator::Evaluator,
 ) -> Result<String, LoxError> {
-    let mut scanner = lox::scanner::Scanner::new(source);
+    let mut scanner = lox::scanner::Scanner::with_file(source, "<stdin>");
     let tokens = scanner.scan_tokens()?;
 
     if tokens.is_empty() {
@@ -126,7 +212,8 @@ fn run_with_evaluator(
     }
 
     let mut parser = lox::parser::Parser::new(tokens);
-    let statements = parser.parse()?;
+    let mut statements = parser.parse()?;
+    lox::resolver::Resolver::new().resolve(&mut statements)?;
 
     if statements.is_empty() {
         return Err(LoxError::ParseError(
@@ -144,11 +231,13 @@ fn run_with_evaluator(
 ///
 /// # 引数
 /// - `source`: 実行するソースコード。
+/// - `file`: エラーの位置に表示するファイル名。
+/// - `strict`: 条件式の厳格モードを有効にするかどうか。
 ///
 /// # エラー
-/// トークン化、パース、評価のいずれかでエラーが発生した場合に `LoxError` を返します。
-fn run(source: &str) -> Result<String, LoxError> {
-    let mut scanner = lox::scanner::Scanner::new(source);
+/// トークン化、パース、変数解決、評価のいずれかでエラーが発生した場合に `LoxError` を返します。
+fn run(source: &str, file: &str, strict: bool) -> Result<String, LoxError> {
+    let mut scanner = lox::scanner::Scanner::with_file(source, file);
     let tokens = scanner.scan_tokens()?;
 
     if tokens.is_empty() {
@@ -156,7 +245,8 @@ fn run(source: &str) -> Result<String, LoxError> {
     }
 
     let mut parser = lox::parser::Parser::new(tokens);
-    let statements = parser.parse()?;
+    let mut statements = parser.parse()?;
+    lox::resolver::Resolver::new().resolve(&mut statements)?;
 
     if statements.is_empty() {
         return Err(LoxError::ParseError(
@@ -165,6 +255,7 @@ fn run(source: &str) -> Result<String, LoxError> {
     }
 
     let mut evaluator = lox::evaluator::Evaluator::new();
+    evaluator.set_strict_conditions(strict);
 
     // 評価結果を取得
     match evaluator.evaluate_statements(statements) {
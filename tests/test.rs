@@ -1,7 +1,11 @@
+use crafting_interpreter::lox::diagnostic::{Diagnostic, Severity}; // エラーの表示
 use crafting_interpreter::lox::error::LoxError;
 use crafting_interpreter::lox::evaluator::{EvalResult, Evaluator}; // EvaluatorとEvalResultをインポート
 use crafting_interpreter::lox::parser::Parser; // スクリプトパーサー
+use crafting_interpreter::lox::printer::AstPrinter; // 構文木の表示
+use crafting_interpreter::lox::resolver::Resolver; // 変数解決
 use crafting_interpreter::lox::scanner::Scanner;
+use termcolor::Buffer;
 
 #[cfg(test)]
 mod tests {
@@ -11,14 +15,39 @@ mod tests {
 
     /// スクリプトを実行して結果を返すヘルパー関数
     fn run_script(input: &str) -> Result<String, LoxError> {
+        run_script_with(Evaluator::new(), input)
+    }
+
+    /// 条件式の厳格モードでスクリプトを実行して結果を返すヘルパー関数
+    fn run_strict_script(input: &str) -> Result<String, LoxError> {
         let mut evaluator = Evaluator::new();
+        evaluator.set_strict_conditions(true);
+        run_script_with(evaluator, input)
+    }
+
+    /// 発生箇所を除いたエラーメッセージを返すヘルパー関数（複数のエラーは改行で連結する）
+    fn root_message(err: &LoxError) -> String {
+        match err {
+            LoxError::Multiple(errors) => errors
+                .iter()
+                .map(root_message)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            err => err.root().to_string(),
+        }
+    }
 
+    /// 指定した `Evaluator` でスクリプトを実行して結果を返すヘルパー関数
+    fn run_script_with(mut evaluator: Evaluator, input: &str) -> Result<String, LoxError> {
         // スキャナーでトークンを取得
         let tokens = Scanner::new(input).scan_tokens()?; // `LoxError` をそのまま返す
 
         // パーサーでステートメントを取得
         let mut parser = Parser::new(tokens);
-        let statements = parser.parse()?; // `LoxError` をそのまま返す
+        let mut statements = parser.parse()?; // `LoxError` をそのまま返す
+
+        // リゾルバで変数のスコープを解決
+        Resolver::new().resolve(&mut statements)?;
 
         // ステートメントを評価
         match evaluator.evaluate_statements(statements) {
@@ -119,6 +148,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_logical_operators() {
+        let input = r#"
+            var called = false;
+            fun touch() {
+                called = true;
+                return "touched";
+            }
+            print false and touch();
+            print called;
+            print "left" or touch();
+            print called;
+            print false or "fallback";
+            print 1 and 2;
+            if (1 < 2 and 2 < 3) print "both";
+        "#;
+        let expected_output = "false\nfalse\nleft\nfalse\nfallback\n2\nboth";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_break_continue() {
+        let input = r#"
+            for (var i = 0; i < 10; i = i + 1) {
+                if (i < 2) continue;
+                if (i > 4) break;
+                print i;
+            }
+            var j = 0;
+            while (true) {
+                j = j + 1;
+                if (j < 3) continue;
+                print j;
+                break;
+            }
+        "#;
+        let expected_output = "2\n3\n4\n3";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
     #[test]
     fn test_functions() {
         let input = r#"
@@ -186,22 +273,1268 @@ mod tests {
     }
 
     #[test]
-    fn test_error_messages() {
-        let inputs = vec![
-            ("print x;", "[Error: Undefined variable 'x']"), // Undefined variable
-            (
-                "if (123) print \"Invalid\";",
-                "[Error: Non-boolean condition 'Condition must evaluate to a boolean.']",
-            ), // Non-boolean condition
-            ("var a = 10 / 0;", "[Error: Division by zero]"), // Division by zero
-            (
-                "fun f(a, a) { print a; }",
-                "[Error: Parse error 'Duplicate parameter name 'a'.']",
-            ), // Duplicate parameter names
-            (
-                "return 123;",
-                "[Error: Cannot return from outside a function.]",
-            ), // Return outside a function
+    fn test_closures() {
+        let input = r#"
+            fun makeCounter() {
+                var i = 0;
+                fun inc() {
+                    i = i + 1;
+                    return i;
+                }
+                return inc;
+            }
+            var counter = makeCounter();
+            counter();
+            print counter();
+            var other = makeCounter();
+            print other();
+
+            var total = 0;
+            fun add(n) {
+                total = total + n;
+            }
+            add(5);
+            add(7);
+            print total;
+        "#;
+        let expected_output = "2\n1\n12";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_resolver_binding() {
+        let input = r#"
+            var a = "global";
+            {
+                fun showA() {
+                    print a;
+                }
+                showA();
+                var a = "block";
+                showA();
+                print a;
+            }
+        "#;
+        let expected_output = "global\nglobal\nblock";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_classes() {
+        let input = r#"
+            class Counter {
+                init(start) {
+                    this.count = start;
+                }
+                increment() {
+                    this.count = this.count + 1;
+                    return this;
+                }
+            }
+            var counter = Counter(10);
+            counter.increment().increment();
+            print counter.count;
+            var inc = counter.increment;
+            inc();
+            print counter.count;
+            print counter;
+        "#;
+        let expected_output = "12\n13\nCounter instance";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let input = r#"
+            class Animal {
+                init(name) {
+                    this.name = name;
+                }
+                speak() {
+                    return this.name + " makes a sound";
+                }
+            }
+            class Dog < Animal {
+                speak() {
+                    return super.speak() + " (woof)";
+                }
+            }
+            class Puppy < Dog {}
+            print Puppy("Pochi").speak();
+        "#;
+        let expected_output = "Pochi makes a sound (woof)";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_lists() {
+        let input = r#"
+            var xs = [1, 2, 3];
+            print xs[0] + xs[2];
+            xs[1] = "two";
+            print xs;
+            push(xs, 4);
+            print len(xs);
+            print pop(xs);
+            print slice(xs, 1, 3);
+            var alias = xs;
+            push(alias, []);
+            print xs;
+            print len("hello");
+        "#;
+        let expected_output = "4\n[1, two, 3]\n4\n4\n[two, 3]\n[1, two, 3, []]\n5";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_maps() {
+        let input = r#"
+            var config = {"name": "lox", "version": 2, true: "yes"};
+            print config["name"];
+            config["version"] = config["version"] + 1;
+            config[1] = "one";
+            print config;
+            print keys(config);
+            print values({"a": 1, "b": [2]});
+            print has(config, "name");
+            print remove(config, "name");
+            print has(config, "name");
+            print len(config);
+            var empty = {};
+            print empty;
+        "#;
+        let expected_output = "lox\n{true: yes, 1: one, name: lox, version: 3}\n[true, 1, name, version]\n[1, [2]]\ntrue\nlox\nfalse\n3\n{}";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_lambdas() {
+        let input = r#"
+            fun apply(f, a, b) {
+                return f(a, b);
+            }
+            print apply(fun (a, b) { return a + b; }, 3, 4);
+            var offset = 10;
+            var shift = fun (n) { return n + offset; };
+            offset = 20;
+            print shift(1);
+            print shift;
+            fun (x) { print x; }(5);
+        "#;
+        let expected_output = "7\n21\n<fn lambda>\n5";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_default_parameters() {
+        let input = r#"
+            fun greet(name, greeting = "Hello", mark = "!") {
+                return greeting + ", " + name + mark;
+            }
+            print greet("Lox");
+            print greet("Lox", "Hi");
+            var calls = 0;
+            fun counter(step = calls = calls + 1) {
+                return step;
+            }
+            counter();
+            counter();
+            counter(5);
+            print calls;
+            fun range(start, end = start + 10) {
+                return end - start;
+            }
+            print range(5);
+            var scale = fun (x, factor = 2) { return x * factor; };
+            print scale(4);
+        "#;
+        let expected_output = "Hello, Lox!\nHi, Lox!\n2\n10\n8";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+
+        // デフォルト値の解析エラーは包み直さずにそのまま報告する
+        let err = run_script("fun f(a = ) {}").expect_err("expected an error");
+        assert_eq!(
+            err.root(),
+            &LoxError::ParseError("Unexpected token.".to_string())
+        );
+        assert_eq!(
+            err.to_string(),
+            "[Error: <script>:1:11: Parse error 'Unexpected token.']"
+        );
+    }
+
+    #[test]
+    fn test_variadic_parameters() {
+        let input = r#"
+            fun log(level, ...parts) {
+                print level;
+                print len(parts);
+                return parts;
+            }
+            print log("info", 1, 2, 3);
+            print log("warn");
+            fun add(a, b, c) {
+                return a + b + c;
+            }
+            var args = [1, 2];
+            print add(...args, 3);
+            print add(0, ...[4, 5]);
+            fun count(...items) {
+                return len(items);
+            }
+            print count(...args, ...args, 9);
+        "#;
+        let expected_output = "info\n3\n[1, 2, 3]\nwarn\n0\n[]\n6\n9\n5";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_numeric_operators() {
+        let input = r#"
+            print 7 % 3;
+            print -7 % 3;
+            print 7 % -3;
+            print 5.5 % 2;
+            print 7 ~/ 2;
+            print -7 ~/ 2;
+            print 2 ** 10;
+            print 2 ** 3 ** 2;
+            print -2 ** 2;
+            print 2 ** -1;
+            print 6 & 3;
+            print 6 | 3;
+            print 6 ^ 3;
+            print ~5;
+            print 1 << 4;
+            print -16 >> 2;
+            print 1 + 2 << 1;
+            print 2 ** 53 | 0;
+            print -(2 ** 53) & -1;
+        "#;
+        let expected_output = "1\n2\n-2\n1.5\n3\n-4\n1024\n512\n-4\n0.5\n2\n7\n5\n-6\n16\n-4\n6\n9007199254740992\n-9007199254740992";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_equality() {
+        let input = r#"
+            fun nothing() {}
+            print nothing() == nothing();
+            print nothing() == false;
+            print 1 == 1.0;
+            print "a" == "a";
+            print "1" == 1;
+            print true != false;
+            var nan = (-1) ** 0.5;
+            print nan == nan;
+            print nan != nan;
+            class Point {}
+            var p = Point();
+            var q = p;
+            print p == q;
+            print p == Point();
+            print nothing == nothing;
+            fun make() { fun inner() {} return inner; }
+            print make() == make();
+            print [1, [2]] == [1, [2]];
+            print {"a": 1} != {"a": 2};
+            var First = Point;
+            print First == Point;
+            class Point {}
+            print First == Point;
+            fun twin() { return 1; }
+            var original = twin;
+            fun twin() { return 1; }
+            print original == twin;
+            class Counter { count() { return 0; } }
+            var counter = Counter();
+            print counter.count == counter.count;
+            print counter.count == Counter().count;
+        "#;
+        let expected_output = "true\nfalse\ntrue\ntrue\nfalse\ntrue\nfalse\ntrue\ntrue\nfalse\ntrue\nfalse\ntrue\ntrue\ntrue\nfalse\nfalse\ntrue\nfalse";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_truthiness() {
+        let input = r#"
+            var missing = nil;
+            print missing;
+            if (missing) print "no"; else print "nil is falsey";
+            if (0) print "zero is truthy";
+            if ("") print "empty string is truthy";
+            var countdown = 2;
+            while (countdown) {
+                print countdown;
+                if (countdown == 1) countdown = nil;
+                else countdown = countdown - 1;
+            }
+            print nil == nil;
+            print !nil;
+        "#;
+        let expected_output =
+            "Nil\nnil is falsey\nzero is truthy\nempty string is truthy\n2\n1\ntrue\ntrue";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_strict_conditions() {
+        let output = run_strict_script("if (true) print \"ok\";");
+        assert_eq!(output.ok().as_deref(), Some("ok"));
+
+        for input in ["if (123) print \"Invalid\";", "while (nil) {}"] {
+            match run_strict_script(input) {
+                Ok(result) => panic!("Expected error but got result: {}", result),
+                Err(err) => assert!(
+                    root_message(&err).contains(
+                        "[Error: Non-boolean condition 'Condition must evaluate to a boolean.']"
+                    ),
+                    "Test failed for input: {}\nGot: {}",
+                    input,
+                    err
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let input = r#"
+            var a = 10;
+            a += 5;
+            a -= 3;
+            a *= 2;
+            a /= 4;
+            a %= 4;
+            print a;
+            var s = "foo";
+            s += "bar";
+            print s;
+            var i = 0;
+            print i++;
+            print i;
+            print ++i;
+            print --i;
+            print i--;
+            print i;
+            class Box {}
+            var box = Box();
+            box.count = 1;
+            var lookups = 0;
+            fun get() {
+                lookups += 1;
+                return box;
+            }
+            get().count += 10;
+            get().count++;
+            print box.count;
+            print lookups;
+            var xs = [1, 2];
+            xs[0] += 5;
+            ++xs[1];
+            print xs;
+            for (var j = 0; j < 3; j++) print j;
+        "#;
+        let expected_output = "2\nfoobar\n0\n1\n2\n1\n1\n0\n12\n2\n[6, 3]\n0\n1\n2";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_conditional_expression() {
+        let input = r#"
+            var n = 1;
+            var label = n == 1 ? "item" : "items";
+            print label;
+            fun size(x) {
+                return x < 10 ? "small" : x < 100 ? "medium" : "large";
+            }
+            print size(5);
+            print size(50);
+            print size(500);
+            var calls = 0;
+            fun touch() {
+                calls += 1;
+                return calls;
+            }
+            print true ? 1 : touch();
+            print calls;
+            var picked;
+            picked = nil ? "yes" : "no";
+            print picked;
+        "#;
+        let expected_output = "item\nsmall\nmedium\nlarge\n1\n0\nno";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let input = r#"
+            var name = "Lox";
+            var count = 2;
+            print "Hello ${name}, you have ${count + 1} items";
+            print "${count}";
+            print "list: ${[1, 2]} map: ${{"k": true}} nil: ${nil}";
+            print "nested ${"inner ${name + "!"}"} done";
+            print "price: $${count}";
+        "#;
+        let expected_output = "Hello Lox, you have 3 items\n2\nlist: [1, 2] map: {k: true} nil: Nil\nnested inner Lox! done\nprice: $2";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let input = r#"
+            print "tab:\t|quote:\"|backslash:\\|dollar:\${x}";
+            print "line1\nline2";
+            print "smile: \u{1F600} \u{41}";
+            print r"C:\path\${raw}";
+            var name = "Lox";
+            var text = """
+                Hello ${name},
+                  indented
+                bye
+                """;
+            print text;
+            print """one line""";
+        "#;
+        let expected_output =
+            "tab:\t|quote:\"|backslash:\\|dollar:${x}\nline1\nline2\nsmile: 😀 A\nC:\\path\\${raw}\nHello Lox,\n  indented\nbye\none line";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+
+        // 複数行の文字列の後のトークンの行番号が正しいことを確認
+        let tokens = Scanner::new("var a = \"\"\"\n  x\n  y\n  \"\"\";\nvar b = \"p\\nq\";\nb;")
+            .scan_tokens()
+            .expect("scan failed");
+        let b = tokens.iter().find(|token| token.lexeme == "b").unwrap();
+        assert_eq!(b.line, 5);
+        let last = tokens
+            .iter()
+            .rev()
+            .find(|token| token.lexeme == "b")
+            .unwrap();
+        assert_eq!(last.line, 6);
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let input = r#"
+            print 0xFF;
+            print 0Xff;
+            print 0b1010;
+            print 0o17;
+            print 1_000_000;
+            print 1.5e-3;
+            print 2E3;
+            print 1e+2;
+            print 3.25;
+            print 1_0.0_5;
+            print 0xFFFFFFFFFFFFFFFF;
+            print 1e20;
+            print -1e20;
+            print 9_007_199_254_740_991;
+            print 2 ** 70;
+        "#;
+        let expected_output = "255\n255\n10\n15\n1000000\n0.0015\n2000\n100\n3.25\n10.05\n\
+            18446744073709552000\n100000000000000000000\n-100000000000000000000\n\
+            9007199254740991\n1180591620717411300000";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_unicode_source() {
+        let input = r#"
+            // 日本語のコメント
+            var 挨拶 = "こんにちは"; /* ブロック — コメント */
+            var café_2 = 3;
+            fun 二倍(値) { return 値 * 2; }
+            print 挨拶;
+            print 二倍(café_2);
+        "#;
+        let expected_output = "こんにちは\n6";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+
+        // BOM と CRLF の扱い
+        let output =
+            run_script("\u{feff}var a = \"x\r\ny\";\r\nprint a;\r\nprint r\"p\r\nq\";\r\n");
+        assert_eq!(output.expect("script failed"), "x\ny\np\nq");
+
+        // 列番号はバイトではなく文字単位で数える
+        let tokens = Scanner::new("\u{feff}var 名前 = \"値\";\r\n  名前;")
+            .scan_tokens()
+            .expect("scan failed");
+        let columns: Vec<(usize, usize)> = tokens
+            .iter()
+            .map(|token| (token.line, token.column))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                (1, 1),
+                (1, 5),
+                (1, 8),
+                (1, 10),
+                (1, 13),
+                (2, 3),
+                (2, 5),
+                (2, 6)
+            ]
+        );
+
+        // 結合文字を含む識別子（NFD の `café`、デーヴァナーガリーのヴィラーマ）は XID_Continue として受け付ける
+        let output = run_script("var cafe\u{301} = 1;\nvar क्ष = 2;\nprint cafe\u{301} + क्ष;");
+        assert_eq!(output.expect("script failed"), "3");
+
+        // XID に含まれない文字（上付きの `²`）は識別子に使用できない
+        let err = run_script("var x² = 1;").expect_err("expected an error");
+        assert_eq!(err.root(), &LoxError::UnexpectedCharacter('²'));
+    }
+
+    #[test]
+    fn test_error_locations() {
+        let inputs = vec![
+            (
+                "var s = \"abc;",
+                "[Error: <script>:1:9: Unterminated string",
+            ), // スキャナーのエラー
+            (
+                "var a = 1;\nprint a + ;",
+                "[Error: <script>:2:11: Parse error 'Unexpected token.']",
+            ), // パーサーのエラー
+            (
+                "var a = 1;\nprint (a;",
+                "[Error: <script>:2:9: Parse error 'Expected ')' after expression.']",
+            ), // `consume` のエラー
+            (
+                "{ var a = 1;\n  var a = 2; }",
+                "[Error: <script>:2:7: Resolve error 'Already a variable named 'a' in this scope.']",
+            ), // リゾルバのエラー
+            (
+                "fun f(x) {\n  return x / 0;\n}\nprint f(1);",
+                "[Error: <script>:2:12: Division by zero]",
+            ), // 関数内の実行時エラー
+            (
+                "var 名前 = \"値\";\nprint 名前 - 1;",
+                "[Error: <script>:2:10: Invalid type conversion",
+            ), // マルチバイト文字を含む行の列番号
+            (
+                "print len(1, 2);",
+                "[Error: <script>:1:15: Runtime error 'len() expected 1 arguments but got 2.']",
+            ), // ネイティブ関数の呼び出しエラー
+        ];
+
+        for (input, expected_error) in inputs {
+            match run_script(input) {
+                Ok(result) => panic!("Expected error but got result: {}", result),
+                Err(err) => assert!(
+                    err.to_string().starts_with(expected_error),
+                    "Test failed for input: {}\nExpected: {}\nGot: {}",
+                    input,
+                    expected_error,
+                    err
+                ),
+            }
+        }
+
+        // エラーの位置はファイル名と範囲を持つ
+        let tokens = Scanner::with_file("var total = 1;\nprint total.x;", "main.lox")
+            .scan_tokens()
+            .expect("scan failed");
+        let mut statements = Parser::new(tokens).parse().expect("parse failed");
+        Resolver::new()
+            .resolve(&mut statements)
+            .expect("resolve failed");
+        let mut evaluator = Evaluator::new();
+        match evaluator.evaluate_statements(statements) {
+            EvalResult::Error(err) => {
+                let span = err.span().expect("error without location");
+                assert_eq!(span.to_string(), "main.lox:2:13");
+                assert_eq!(span.length, 1);
+            }
+            EvalResult::Return(_) => panic!("Expected error"),
+        }
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        // 発生箇所の行と下線を表示する
+        let source = "var 名前 = 1;\nprint 名前 + 未定義;";
+        let err = run_script(source).expect_err("expected an error");
+        let mut buffer = Buffer::no_color();
+        Diagnostic::from(&err)
+            .render(source, &mut buffer)
+            .expect("render failed");
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "error: Undefined variable '未定義'\n \
+             --> <script>:2:12\n  \
+             |\n\
+             2 | print 名前 + 未定義;\n  \
+             |              ^^^^^^\n  \
+             = help: declare '未定義' with 'var' before using it\n"
+        );
+
+        // 発生箇所のない診断と補足
+        let mut buffer = Buffer::no_color();
+        Diagnostic::new(Severity::Warning, "unused variable 'x'")
+            .with_note("declared here")
+            .render("", &mut buffer)
+            .expect("render failed");
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "warning: unused variable 'x'\n = note: declared here\n"
+        );
+
+        // 色付きの出力には重大度の色（赤）が含まれる
+        let mut buffer = Buffer::ansi();
+        Diagnostic::from(&LoxError::DivisionByZero)
+            .render("", &mut buffer)
+            .expect("render failed");
+        let output = String::from_utf8(buffer.into_inner()).unwrap();
+        assert!(output.contains("\x1b[31merror"), "got: {:?}", output);
+    }
+
+    #[test]
+    fn test_parser_recovery() {
+        let input =
+            "var a = ;\nprint 1;\nfun f( { }\nvar b = 2;\nprint b +;\nwhile (true) { print a; }";
+        let tokens = Scanner::new(input).scan_tokens().expect("scan failed");
+
+        // エラーのあった文を読み飛ばし、残りの文を解析する
+        let (statements, errors) = Parser::new(tokens.clone()).parse_with_recovery();
+        assert_eq!(statements.len(), 3, "partial AST: {:?}", statements);
+        let locations: Vec<String> = errors
+            .iter()
+            .map(|err| err.span().expect("error without location").to_string())
+            .collect();
+        assert_eq!(
+            locations,
+            vec!["<script>:1:9", "<script>:3:8", "<script>:5:10"]
+        );
+
+        // `parse` はすべてのエラーをまとめて返す
+        match Parser::new(tokens).parse() {
+            Err(LoxError::Multiple(errors)) => assert_eq!(errors.len(), 3),
+            result => panic!("Expected multiple errors but got: {:?}", result),
+        }
+        let err = run_script(input).expect_err("expected an error");
+        assert_eq!(
+            err.to_string(),
+            "[Error: <script>:1:9: Parse error 'Unexpected token.']\n\
+             [Error: <script>:3:8: Parse error 'Expect parameter name.']\n\
+             [Error: <script>:5:10: Parse error 'Unexpected token.']"
+        );
+
+        // ブロックや関数本体の中のエラーもその文だけを読み飛ばし、後続の `}` で連鎖的なエラーを起こさない
+        let cases = vec![
+            ("{ var a = ; print 1; }", vec!["<script>:1:11"]),
+            ("{ var a = 1 }\nprint 2;", vec!["<script>:1:13"]),
+            (
+                "fun f() {\n  var a = ;\n  var b = 1 +;\n  return a;\n}\nvar c = ;",
+                vec!["<script>:2:11", "<script>:3:14", "<script>:6:9"],
+            ),
+            ("fun f() { return 1 }\nprint f();", vec!["<script>:1:20"]),
+            (
+                "while (true) { if (x) { var = 1; } break; }",
+                vec!["<script>:1:29"],
+            ),
+        ];
+        for (input, expected) in cases {
+            let tokens = Scanner::new(input).scan_tokens().expect("scan failed");
+            let (_, errors) = Parser::new(tokens).parse_with_recovery();
+            let locations: Vec<String> = errors
+                .iter()
+                .map(|err| err.span().expect("error without location").to_string())
+                .collect();
+            assert_eq!(locations, expected, "Test failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_stack_traces() {
+        let input = "fun inner(x) {\n  return x / 0;\n}\nfun outer(x) {\n  return inner(x) + 1;\n}\nclass Box {\n  open() { return outer(1); }\n}\nprint Box().open();";
+        let err = run_script(input).expect_err("expected an error");
+
+        // コールスタックは最も内側の呼び出しから順に、関数名と呼び出し位置を持つ
+        let frames: Vec<(&str, usize)> = err
+            .trace()
+            .iter()
+            .map(|frame| (frame.function.as_str(), frame.call_site.line))
+            .collect();
+        assert_eq!(frames, vec![("inner", 5), ("outer", 8), ("Box.open", 10)]);
+        assert_eq!(err.root(), &LoxError::DivisionByZero);
+        assert_eq!(
+            err.to_string(),
+            "[Error: <script>:2:12: Division by zero]\n    \
+             at inner (<script>:5)\n    \
+             at outer (<script>:8)\n    \
+             at Box.open (<script>:10)"
+        );
+
+        // 関数の外で発生したエラーはコールスタックを持たない
+        let err = run_script("print 1 / 0;").expect_err("expected an error");
+        assert!(err.trace().is_empty());
+
+        // ネイティブ関数の呼び出しも記録される
+        let err =
+            run_script("fun f(a) { return pop(a); }\nprint f([]);").expect_err("expected an error");
+        let frames: Vec<&str> = err
+            .trace()
+            .iter()
+            .map(|frame| frame.function.as_str())
+            .collect();
+        assert_eq!(frames, vec!["pop", "f"]);
+
+        // 引数の数の誤りは呼び出し位置のエラーであり、呼び出し先のフレームを持たない
+        let err = run_script("fun f(a) {}\nf(1, 2);").expect_err("expected an error");
+        assert!(err.trace().is_empty());
+        assert_eq!(
+            err.to_string(),
+            "[Error: <script>:2:7: Invalid type conversion 'Expected 1 arguments but got 2.']"
+        );
+        let err = run_script("fun f(a) {}\nfun g() { f(); }\ng();").expect_err("expected an error");
+        let frames: Vec<&str> = err
+            .trace()
+            .iter()
+            .map(|frame| frame.function.as_str())
+            .collect();
+        assert_eq!(frames, vec!["g"]);
+        let err = run_script("class A {}\nA(1);").expect_err("expected an error");
+        assert!(err.trace().is_empty());
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        // 無限再帰はホストのスタックを溢れさせず、実行時エラーになる
+        let err =
+            run_script("fun f(n) { return f(n + 1); }\nf(0);").expect_err("expected an error");
+        assert_eq!(
+            err.root(),
+            &LoxError::RuntimeError("Stack overflow.".to_string())
+        );
+        assert_eq!(err.trace().len(), 1000);
+        assert!(err.trace().iter().all(|frame| frame.function == "f"));
+        // 表示では連続する同じフレームをまとめる
+        assert_eq!(
+            err.to_string(),
+            "[Error: <script>:1:26: Runtime error 'Stack overflow.']\n    \
+             at f (<script>:1)\n    \
+             ... repeated 998 more times\n    \
+             at f (<script>:2)"
+        );
+
+        // スタックオーバーフローは捕捉できる
+        let output = run_script(
+            "fun f(n) { return f(n + 1); }\ntry { f(0); } catch (e) { print e.message; }",
+        )
+        .expect("expected the error to be caught");
+        assert_eq!(output, "Stack overflow.");
+
+        // 上限より浅い再帰は通常どおり評価される
+        let output = run_script(
+            "fun sum(n) { if (n == 0) return 0; return n + sum(n - 1); }\nprint sum(900);",
+        )
+        .expect("expected deep recursion to succeed");
+        assert_eq!(output, "405450");
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let input = r#"
+            fun check(n) {
+                if (n < 0) throw "negative: ${n}";
+                return n;
+            }
+            try {
+                check(-1);
+                print "unreachable";
+            } catch (e) {
+                print e;
+            }
+            try {
+                var x = 1 / 0;
+            } catch (e) {
+                print e.message;
+                print e.line;
+            }
+            fun cleanup() {
+                try {
+                    return "body";
+                } finally {
+                    print "finally";
+                }
+            }
+            print cleanup();
+            for (var i = 0; i < 3; i++) {
+                try {
+                    if (i == 1) continue;
+                    print i;
+                } finally {
+                    print "after ${i}";
+                }
+            }
+            try {
+                try {
+                    throw "inner";
+                } finally {
+                    print "inner finally";
+                }
+            } catch (e) {
+                print "outer caught ${e}";
+            }
+        "#;
+        let expected_output = "negative: -1\nDivision by zero\n13\nfinally\nbody\n0\nafter 0\nafter 1\n2\nafter 2\ninner finally\nouter caught inner";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    /// スクリプトを解析し、各文を `AstPrinter` で文字列化して返すヘルパー関数
+    fn print_ast(input: &str) -> Vec<String> {
+        let tokens = Scanner::new(input).scan_tokens().expect("scan failed");
+        let statements = Parser::new(tokens).parse().expect("parse failed");
+        statements
+            .iter()
+            .map(|stmt| stmt.accept(&mut AstPrinter))
+            .collect()
+    }
+
+    #[test]
+    fn test_error_inside_print() {
+        // print の中で発生して捕捉されたエラーは出力に残らない
+        let output = run_script("try { print 1 / 0; } catch (e) { print \"caught\"; }")
+            .expect("expected the error to be caught");
+        assert_eq!(output, "caught");
+
+        let output = run_script("print 1;\ntry { print 1 / 0; } catch (e) { print e.message; }")
+            .expect("expected the error to be caught");
+        assert_eq!(output, "1\nDivision by zero");
+    }
+
+    #[test]
+    fn test_ast_printer() {
+        let cases = vec![
+            ("a and b or c;", "(or (and a b) c)"), // 論理演算子
+            (
+                "while (true) { break; continue; }",
+                "(while true (block (break) (continue)))",
+            ), // break / continue
+            (
+                "var f = fun (a, b = 1) { return a + b; };",
+                "(var f = (fun (a, b = 1) (return (+ a b))))",
+            ), // 無名関数
+            ("2 ** 3 ** 2;", "(** 2 (** 3 2))"),   // べき乗
+            ("7 ~/ 2;", "(~/ 7 2)"),               // 整数除算
+            ("a & b | c ^ d;", "(| (& a b) (^ c d))"), // ビット演算
+            ("~a << 1 >> 2;", "(>> (<< (~ a) 1) 2)"), // ビット反転とシフト
+            ("n == 1 ? \"item\" : \"items\";", "(? (== n 1) item items)"), // 条件演算子
+            ("\"Hello ${name}!\";", "(interpolate Hello  name !)"), // 文字列補間
+            ("throw \"oops\";", "(throw oops)"),   // throw 文
+            (
+                "try { f(); } catch (e) { print e; } finally { g(); }",
+                "(try (block (call f )) (catch e (block (print e))) (finally (block (call g ))))",
+            ), // try 文
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                print_ast(input),
+                vec![expected],
+                "Test failed for input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_trailing_comments() {
+        let input = r#"
+            // 行頭のコメント
+            var a = 17; // 文の後のコメント
+            var b = a // 値の後のコメント
+                ~/ 5;
+            print b;
+            var xs = [
+                1 // one
+            ];
+            print xs;
+            var config = { "debug": true // enable debug
+            };
+            print config["debug"];
+            fun half(n) // 関数宣言の見出しの後のコメント
+            {
+                return n ~/ 2;
+            }
+            print half(9);
+            class Point {
+                init(x) // constructor
+                {
+                    this.x = x;
+                }
+            }
+            print Point(4).x;
+            if (a > 10) // 見出しの後のコメント
+                print "big";
+        "#;
+        let expected_output = "3\n[1]\ntrue\n4\n4\nbig";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_caught_error_messages() {
+        let input = r#"
+            try { nil(); } catch (e) { print e.message; }
+            try { print missing; } catch (e) { print e.message; }
+            try { [1][5]; } catch (e) { print e.message; }
+            try { print 1 / 0; } catch (e) { print e; }
+        "#;
+        let expected_output = "Can only call functions.\nUndefined variable 'missing'\nIndex 5 out of range for length 1.\nError instance";
+        let output = run_script(input);
+
+        match output {
+            Ok(actual_output) => assert_eq!(
+                actual_output, expected_output,
+                "Test failed for input: {}",
+                input
+            ),
+            Err(err) => panic!("Test failed with error: {:?} for input: {}", err, input),
+        }
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let inputs = vec![
+            ("print x;", "[Error: Undefined variable 'x']"), // Undefined variable
+            ("var a = 10 / 0;", "[Error: Division by zero]"), // Division by zero
+            (
+                "fun f(a, a) { print a; }",
+                "[Error: Parse error 'Duplicate parameter name 'a'.']",
+            ), // Duplicate parameter names
+            (
+                "return 123;",
+                "[Error: Cannot return from outside a function.]",
+            ), // Return outside a function
+            (
+                "fun f() { fun g() {} } return 1;",
+                "[Error: Cannot return from outside a function.]",
+            ), // Return after a nested function
+            (
+                "{ var a = a; }",
+                "[Error: Resolve error 'Can't read local variable 'a' in its own initializer.']",
+            ), // Local read in its own initializer
+            (
+                "{ var a = 1; var a = 2; }",
+                "[Error: Resolve error 'Already a variable named 'a' in this scope.']",
+            ), // Duplicate declaration in one scope
+            (
+                "break;",
+                "[Error: Parse error 'Can't use 'break' outside of a loop.']",
+            ), // Break outside a loop
+            (
+                "while (true) { fun f() { continue; } }",
+                "[Error: Parse error 'Can't use 'continue' outside of a loop.']",
+            ), // Continue inside a function nested in a loop
+            (
+                "class A < A {}",
+                "[Error: Parse error 'A class can't inherit from itself.']",
+            ), // Class inheriting from itself
+            (
+                "var x = 1; class A < x {}",
+                "[Error: Runtime error 'Superclass must be a class.']",
+            ), // Superclass is not a class
+            (
+                "var xs = [1, 2]; print xs[2];",
+                "[Error: Runtime error 'Index 2 out of range for length 2.']",
+            ), // Index out of range
+            (
+                "print pop([]);",
+                "[Error: Runtime error 'Can't pop from an empty list.']",
+            ), // Pop from an empty list
+            (
+                "var m = {\"a\": 1}; print m[\"b\"];",
+                "[Error: Runtime error 'Undefined key 'b'.']",
+            ), // Missing map key
+            (
+                "var m = {[1]: 2};",
+                "[Error: Runtime error 'Map keys must be strings, numbers or booleans.']",
+            ), // Unsupported map key
+            (
+                "fun f(a = 1, b) {}",
+                "[Error: Parse error 'Parameter 'b' without a default value can't follow parameters with defaults.']",
+            ), // Required parameter after a defaulted one
+            (
+                "fun f(a, b = 1) { return a; } print f();",
+                "Expected 1 to 2 arguments but got 0.",
+            ), // Too few arguments for defaults
+            (
+                "fun f(...a, b) {}",
+                "[Error: Parse error 'Rest parameter must be the last parameter.']",
+            ), // Parameter after a rest parameter
+            (
+                "fun f(a, ...b) { return b; } print f();",
+                "Expected at least 1 arguments but got 0.",
+            ), // Too few arguments for a variadic function
+            (
+                "fun f(a) { return a; } print f(...1);",
+                "[Error: Runtime error 'Only lists can be spread into arguments.']",
+            ), // Spreading a non-list
+            (
+                "var a = 1 % 0;",
+                "[Error: Division by zero]",
+            ), // Modulo by zero
+            (
+                "var a = 1 ~/ 0;",
+                "[Error: Division by zero]",
+            ), // Integer division by zero
+            (
+                "var a = 1.5 & 1;",
+                "Operands must be integers for '&'.",
+            ), // Bitwise operator on a non-integer
+            (
+                "var a = (2 ** 53 + 2) | 1;",
+                "Operands must be integers for '|'.",
+            ), // Bitwise operator beyond 2^53
+            (
+                "var a = ~(2 ** 62);",
+                "Operand must be an integer for '~'.",
+            ), // Bitwise complement beyond 2^53
+            (
+                "var a = 1; 1 += a;",
+                "[Error: Parse error 'Invalid assignment target.']",
+            ), // Invalid compound assignment target
+            (
+                "var s = \"a\"; print s++;",
+                "Operand must be a number for '++'.",
+            ), // Incrementing a non-number
+            (
+                "var a = true ? 1;",
+                "[Error: Parse error 'Expect ':' after then branch of conditional expression.']",
+            ), // Missing ':' in a conditional expression
+            (
+                "print \"value ${1 + 2\";",
+                "[Error: Unterminated string 'Unterminated string interpolation.']",
+            ), // Unterminated interpolation
+            (
+                "print \"bad \\q\";",
+                "[Error: Invalid escape sequence 'Unknown escape sequence '\\q'.']",
+            ), // Unknown escape sequence
+            (
+                "print \"\\u{110000}\";",
+                "[Error: Invalid escape sequence 'Invalid unicode code point '\\u{110000}'.']",
+            ), // Invalid unicode code point
+            (
+                "print 0x;",
+                "[Error: Malformed number literal ''0x' must be followed by hexadecimal digits.']",
+            ), // 接頭辞の後に数字がない
+            (
+                "print 1e;",
+                "[Error: Malformed number literal 'Exponent of '1e' has no digits.']",
+            ), // 指数部に数字がない
+            (
+                "print 0b102;",
+                "[Error: Malformed number literal 'Invalid digit '2' in binary literal.']",
+            ), // 基数に合わない数字
+            (
+                "print 1__000;",
+                "[Error: Malformed number literal ''_' must separate digits.']",
+            ), // 連続した区切り文字
+            (
+                "print 100_;",
+                "[Error: Malformed number literal ''_' must separate digits.']",
+            ), // 末尾の区切り文字
+            (
+                "fun f() { throw \"boom\"; } f();",
+                "[Error: Uncaught exception 'boom']",
+            ), // Uncaught exception
+            (
+                "try { var a = 1 / 0; } catch (e) { throw e; }",
+                "[Error: Uncaught exception 'Division by zero']",
+            ), // Rethrown runtime error
+            (
+                "try { print 1; }",
+                "[Error: Parse error 'Expect 'catch' or 'finally' after try block.']",
+            ), // Try without catch or finally
+            (
+                "try { } catch { }",
+                "[Error: Parse error 'Expect '(' after 'catch'.']",
+            ), // Catch without variable
         ];
 
         for (input, expected_error) in inputs {
@@ -214,7 +1547,7 @@ mod tests {
                 Err(err) => {
                     println!("{}", err); // エラーメッセージを表示
                     assert!(
-                        format!("{}", err).contains(expected_error), // エラーメッセージに期待する文字列が含まれているか確認
+                        root_message(&err).contains(expected_error), // エラーメッセージに期待する文字列が含まれているか確認
                         "Test failed for input: {}\nExpected: {}\nGot: {}",
                         input,
                         expected_error,